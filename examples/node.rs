@@ -10,7 +10,7 @@ pub async fn main() -> anyhow::Result<()> {
         Duration::from_secs(10),
     )?;
     let endpoints = client.endpoints();
-    let extensions = client.extensions();
+    // let extensions = client.extensions();
 
     dbg!(endpoints.wallet()?.unlock("hello".to_owned()).await?);
     // dbg!(endpoints.root()?.info().await?);
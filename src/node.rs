@@ -1,7 +1,36 @@
 pub mod endpoints;
 pub mod extensions;
 
-use self::{endpoints::NodeEndpoint, extensions::NodeExtension};
+use self::{
+    endpoints::{script::CompileError, NodeEndpoint},
+    extensions::{
+        contract::ContractError,
+        contract_cache::{ContractCache, ContractCacheError},
+        eip4::Eip4Error,
+        reservation::BoxReservations,
+        NodeExtension, StateContextCache, NUM_STATE_CONTEXT_HEADERS,
+    },
+};
+use ergo_lib::{
+    chain::{
+        ergo_box::box_builder::ErgoBoxCandidateBuilderError,
+        transaction::ergo_transaction::TxValidationError,
+    },
+    ergo_chain_types::BlockId,
+    ergotree_ir::{
+        chain::{
+            address::AddressEncoderError,
+            ergo_box::{box_value::BoxValueError, BoxId},
+            token::{Token, TokenAmountError},
+        },
+        ergo_tree::ErgoTreeError,
+        serialization::{SigmaParsingError, SigmaSerializationError},
+    },
+    wallet::{
+        box_selector::BoxSelectorError, tx_builder::TxBuilderError,
+        tx_context::TransactionContextError, WalletError,
+    },
+};
 use reqwest::{
     header::{HeaderMap, HeaderValue, InvalidHeaderValue},
     Client, Url,
@@ -11,8 +40,64 @@ use std::time::Duration;
 
 #[derive(thiserror::Error, Debug)]
 pub enum NodeError {
-    #[error("Nodes wallet doesn't hold enough nanoergs, {found} < {requested}")]
-    InsufficientFunds { requested: u64, found: u64 },
+    #[error(
+        "Nodes wallet doesn't hold enough funds, missing {missing_nano_ergs} nanoergs and tokens {missing_tokens:?}"
+    )]
+    InsufficientFunds {
+        missing_nano_ergs: u64,
+        missing_tokens: Vec<Token>,
+    },
+
+    #[error("Failed to select boxes")]
+    BoxSelection(#[from] BoxSelectorError),
+
+    #[error("Invalid box value")]
+    BoxValue(#[from] BoxValueError),
+
+    #[error("Invalid token amount")]
+    TokenAmount(#[from] TokenAmountError),
+
+    #[error("Failed to use contract cache")]
+    ContractCache(#[from] ContractCacheError),
+
+    #[error("Failed to bind contract constants")]
+    Contract(#[from] ContractError),
+
+    #[error("Token metadata doesn't follow EIP-4")]
+    Eip4(#[from] Eip4Error),
+
+    #[error("Box {0} is neither in the UTXO set nor created by a mempool transaction")]
+    BoxNotFound(BoxId),
+
+    #[error("Failed to build transaction context")]
+    TransactionContext(#[from] TransactionContextError),
+
+    #[error("Transaction failed validation")]
+    Validation(#[from] TxValidationError),
+
+    #[error("Failed to sign transaction")]
+    Signing(#[from] WalletError),
+
+    #[error("Failed to build output box")]
+    BoxBuilder(#[from] ErgoBoxCandidateBuilderError),
+
+    #[error("Failed to build transaction")]
+    TxBuilder(#[from] TxBuilderError),
+
+    #[error("Failed to parse address")]
+    Address(#[from] AddressEncoderError),
+
+    #[error("Failed to parse ErgoTree")]
+    SigmaParsing(#[from] SigmaParsingError),
+
+    #[error("Invalid ErgoTree")]
+    ErgoTree(#[from] ErgoTreeError),
+
+    #[error("ErgoTree doesn't match the shape of its address type: {0}")]
+    UnexpectedErgoTree(String),
+
+    #[error("Failed to serialize transaction")]
+    SigmaSerialization(#[from] SigmaSerializationError),
 
     #[error("Specified API key is not a valid header value")]
     InvalidApiKey {
@@ -20,6 +105,9 @@ pub enum NodeError {
         key: String,
     },
 
+    #[error("Failed to compile ErgoScript: {0}")]
+    Compile(CompileError),
+
     /// Node returned a 4xx or 5xx error code.
     ///
     /// Returns the description of the error returned by the node
@@ -41,6 +129,21 @@ pub enum NodeError {
 
     #[error("Invalid base URL provided")]
     BaseUrl,
+
+    #[error("Node returned {0} headers, {n} are needed to build a state context", n = NUM_STATE_CONTEXT_HEADERS)]
+    NotEnoughHeaders(usize),
+
+    #[error("Scan {0} is not registered")]
+    ScanNotFound(u32),
+
+    #[error("Scan name {0} is used by more than one scan")]
+    DuplicateScanName(String),
+
+    #[error("Node hasn't applied any blocks yet")]
+    NoBlocks,
+
+    #[error("Checkpoint block {block_id:?} is not at height {height}")]
+    InvalidCheckpoint { height: u32, block_id: BlockId },
 }
 
 /// Error object returned by the nodes API.
@@ -64,13 +167,16 @@ pub(crate) async fn process_response<T: DeserializeOwned>(
             .json::<NodeApiError>()
             .await
             .map_err(NodeError::Json)?;
-        Err(NodeError::BadRequest(node_err.detail).into())
+        Err(NodeError::BadRequest(node_err.detail))
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct NodeClient {
     endpoints: NodeEndpoint,
+    state_context_cache: StateContextCache,
+    box_reservations: BoxReservations,
+    contract_cache: Option<ContractCache>,
 }
 
 impl NodeClient {
@@ -98,14 +204,36 @@ impl NodeClient {
             .map_err(NodeError::Client)?;
         Ok(Self {
             endpoints: NodeEndpoint::new(client, url),
+            state_context_cache: StateContextCache::default(),
+            box_reservations: BoxReservations::default(),
+            contract_cache: None,
         })
     }
 
+    /// Replaces the client's box reservations, e.g. to change their TTLs or to share them
+    /// with another client using the same wallet
+    pub fn with_box_reservations(mut self, box_reservations: BoxReservations) -> Self {
+        self.box_reservations = box_reservations;
+        self
+    }
+
+    /// Caches contracts compiled through [`Self::extensions`] in `contract_cache`
+    pub fn with_contract_cache(mut self, contract_cache: ContractCache) -> Self {
+        self.contract_cache = Some(contract_cache);
+        self
+    }
+
     pub fn endpoints(&self) -> &NodeEndpoint {
         &self.endpoints
     }
 
-    pub fn extensions(&self) -> NodeExtension {
-        NodeExtension::new(&self.endpoints)
+    pub fn extensions(&self) -> NodeExtension<'_> {
+        let extensions = NodeExtension::new(&self.endpoints)
+            .with_state_context_cache(&self.state_context_cache)
+            .with_box_reservations(&self.box_reservations);
+        match &self.contract_cache {
+            Some(contract_cache) => extensions.with_contract_cache(contract_cache),
+            None => extensions,
+        }
     }
 }
@@ -1,10 +1,12 @@
 pub mod blockchain;
 pub mod blocks;
+pub mod emission;
 pub mod root;
 pub mod scan;
 pub mod script;
 pub mod transactions;
 pub mod utils;
+pub mod utxo;
 pub mod wallet;
 
 use self::root::RootEndpoint;
@@ -13,18 +15,20 @@ use self::transactions::TransactionsEndpoint;
 use self::wallet::WalletEndpoint;
 use blockchain::BlockchainEndpoint;
 use blocks::BlocksEndpoint;
+use emission::EmissionEndpoint;
 use reqwest::{Client, Url};
 use scan::ScanEndpoint;
 use serde::Serialize;
 use utils::UtilsEndpoint;
+use utxo::UtxoEndpoint;
 
 use super::NodeError;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NodePaginationQuery {
-    limit: u32,
-    offset: u32,
+    pub limit: u32,
+    pub offset: u32,
 }
 
 impl Default for NodePaginationQuery {
@@ -51,35 +55,43 @@ impl NodeEndpoint {
         &self.url
     }
 
-    pub fn root(&self) -> Result<RootEndpoint, NodeError> {
+    pub fn root(&self) -> Result<RootEndpoint<'_>, NodeError> {
         RootEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn blocks(&self) -> Result<BlocksEndpoint, NodeError> {
+    pub fn blocks(&self) -> Result<BlocksEndpoint<'_>, NodeError> {
         BlocksEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn wallet(&self) -> Result<WalletEndpoint, NodeError> {
+    pub fn wallet(&self) -> Result<WalletEndpoint<'_>, NodeError> {
         WalletEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn transactions(&self) -> Result<TransactionsEndpoint, NodeError> {
+    pub fn transactions(&self) -> Result<TransactionsEndpoint<'_>, NodeError> {
         TransactionsEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn script(&self) -> Result<ScriptEndpoint, NodeError> {
+    pub fn script(&self) -> Result<ScriptEndpoint<'_>, NodeError> {
         ScriptEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn utils(&self) -> Result<UtilsEndpoint, NodeError> {
+    pub fn utils(&self) -> Result<UtilsEndpoint<'_>, NodeError> {
         UtilsEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn blockchain(&self) -> Result<BlockchainEndpoint, NodeError> {
+    pub fn blockchain(&self) -> Result<BlockchainEndpoint<'_>, NodeError> {
         BlockchainEndpoint::new(&self.client, self.url.clone())
     }
 
-    pub fn scan(&self) -> Result<ScanEndpoint, NodeError> {
+    pub fn scan(&self) -> Result<ScanEndpoint<'_>, NodeError> {
         ScanEndpoint::new(&self.client, self.url.clone())
     }
+
+    pub fn utxo(&self) -> Result<UtxoEndpoint<'_>, NodeError> {
+        UtxoEndpoint::new(&self.client, self.url.clone())
+    }
+
+    pub fn emission(&self) -> Result<EmissionEndpoint<'_>, NodeError> {
+        EmissionEndpoint::new(&self.client, self.url.clone())
+    }
 }
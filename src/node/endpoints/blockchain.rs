@@ -6,9 +6,8 @@ use ergo_lib::{
         token::TokenId,
     },
 };
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use url::Url;
 
 use crate::node::{process_response, NodeError};
@@ -45,11 +44,28 @@ pub struct IndexedBox {
 pub struct IndexedTransaction {
     pub id: TxId,
     pub block_id: BlockId,
+    pub inclusion_height: u32,
+    pub num_confirmations: u32,
     pub inputs: Vec<ErgoBox>,
     pub outputs: Vec<ErgoBox>,
     pub data_inputs: Vec<DataInput>,
 }
 
+/// Token info returned by the indexer. `name`, `description` and `decimals` are only set if the
+/// node could decode them from the issuance box, see [`crate::node::extensions::eip4`] for the full
+/// EIP-4 metadata
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedToken {
+    pub id: TokenId,
+    /// Id of the box the token was issued in
+    pub box_id: BoxId,
+    pub emission_amount: u64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub decimals: Option<u32>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IndexedHeight {
@@ -117,6 +133,24 @@ impl<'a> BlockchainEndpoint<'a> {
         process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
     }
 
+    /// Like [`Self::get_transaction_by_id`], but returns `None` if the indexer doesn't know the
+    /// transaction. Other errors, such as a disabled indexer, are still returned
+    pub async fn find_transaction_by_id(
+        &self,
+        tx_id: &TxId,
+    ) -> Result<Option<IndexedTransaction>, NodeError> {
+        let mut url = self.url.clone();
+        let tx_id = tx_id.to_string();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["transaction", "byId", &tx_id]);
+        let response = self.client.get(url).send().await.map_err(NodeError::Http)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        process_response(response).await.map(Some)
+    }
+
     pub async fn get_box_by_id(&self, box_id: &BoxId) -> Result<IndexedBox, NodeError> {
         let mut url = self.url.clone();
         let box_id = box_id.to_string();
@@ -126,6 +160,15 @@ impl<'a> BlockchainEndpoint<'a> {
         process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
     }
 
+    pub async fn get_token_by_id(&self, token_id: &TokenId) -> Result<IndexedToken, NodeError> {
+        let mut url = self.url.clone();
+        let token_id = String::from(*token_id);
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["token", "byId", &token_id]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
     pub async fn get_unspent_boxes_by_token_id(
         &self,
         token_id: &str,
@@ -134,8 +177,8 @@ impl<'a> BlockchainEndpoint<'a> {
         let mut url = self.url.clone();
         url.path_segments_mut()
             .map_err(|_| NodeError::BaseUrl)?
-            .extend(&["box", "unspent", "byTokenId", &token_id]);
-        Ok(process_response(
+            .extend(&["box", "unspent", "byTokenId", token_id]);
+        process_response(
             self.client
                 .get(url)
                 .query(&index_query)
@@ -143,6 +186,6 @@ impl<'a> BlockchainEndpoint<'a> {
                 .await
                 .map_err(NodeError::Http)?,
         )
-        .await?)
+        .await
     }
 }
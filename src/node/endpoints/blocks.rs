@@ -34,16 +34,34 @@ impl<'a> BlocksEndpoint<'a> {
             self.client.get(url).send().await.map_err(NodeError::Http)?,
         )
         .await?
-        .get(0)
+        .first()
         .cloned())
     }
 
+    /// Get a header by its id, which may also be a header of a fork (/blocks/{headerId}/header)
+    pub async fn header(&self, block_id: &BlockId) -> Result<Header, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&[&block_id.to_string(), "header"]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
+    /// Get the last `count` headers of the best chain, oldest first (/blocks/lastHeaders/{count})
+    pub async fn last_headers(&self, count: u32) -> Result<Vec<Header>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["lastHeaders", &format!("{count}")]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
     pub async fn chain_slice(&self, range: Range<u32>) -> Result<Vec<Header>, NodeError> {
         let mut url = self.url.clone();
         url.path_segments_mut()
             .map_err(|_| NodeError::BaseUrl)?
             .push("chainSlice");
-        Ok(process_response(
+        process_response(
             self.client
                 .get(url)
                 .query(&[("fromHeight", range.start), ("toHeight", range.end)])
@@ -51,7 +69,7 @@ impl<'a> BlocksEndpoint<'a> {
                 .await
                 .map_err(NodeError::Http)?,
         )
-        .await?)
+        .await
     }
 
     pub async fn transactions(&self, block_id: &BlockId) -> Result<Vec<Transaction>, NodeError> {
@@ -0,0 +1,70 @@
+pub mod schedule;
+
+use ergo_lib::ergotree_ir::chain::address::NetworkAddress;
+use reqwest::Client;
+use serde::Deserialize;
+use url::Url;
+
+use crate::node::{process_response, NodeError};
+
+#[derive(Debug, Clone)]
+pub struct EmissionEndpoint<'a> {
+    client: &'a Client,
+    url: Url,
+}
+
+impl<'a> EmissionEndpoint<'a> {
+    pub fn new(client: &'a Client, mut url: Url) -> Result<Self, NodeError> {
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("emission");
+        Ok(Self { client, url })
+    }
+}
+
+/// Emission state at a height, amounts in nanoergs.
+/// [`schedule::EmissionSchedule::info_at`] computes the same values locally
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmissionInfo {
+    /// Reward the block's miner keeps, i.e. after the EIP-27 re-emission charge
+    pub miner_reward: u64,
+    /// Coins emitted up to and including the block
+    pub total_coins_issued: u64,
+    /// Coins left in the emission contract after the block
+    pub total_remain_coins: u64,
+    /// EIP-27 re-emission charge taken out of the block's emission
+    #[serde(default)]
+    pub reemitted: u64,
+}
+
+/// Addresses of the contracts involved in emission
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EmissionScripts {
+    pub emission: NetworkAddress,
+    /// Contract holding the coins charged for re-emission (EIP-27)
+    pub reemission: NetworkAddress,
+    /// Contract miners send the re-emission charge to
+    pub pay2_reemission: NetworkAddress,
+}
+
+impl<'a> EmissionEndpoint<'a> {
+    /// Get the emission state at `height` (/emission/at/{height} endpoint)
+    pub async fn at(&self, height: u32) -> Result<EmissionInfo, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["at", &height.to_string()]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
+    /// Get the addresses of the emission contracts (/emission/scripts endpoint)
+    pub async fn scripts(&self) -> Result<EmissionScripts, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("scripts");
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+}
@@ -0,0 +1,395 @@
+//! Local computation of the emission schedule following the node's emission rules and the
+//! EIP-27 re-emission rules, e.g. to compute the circulating supply at any height without
+//! a request per height. [`EmissionSchedule::info_at`] can be cross-checked against
+//! [`super::EmissionEndpoint::at`]
+use super::EmissionInfo;
+
+pub const NANO_ERGS_PER_ERG: u64 = 1_000_000_000;
+
+/// EIP-27 re-emission: part of every block's emission is sent to the re-emission contract,
+/// which pays it out to miners once regular emission is over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReemissionRules {
+    /// First height the re-emission charge is taken at
+    pub activation_height: u32,
+    /// First height the re-emission contract pays out at
+    pub start_height: u32,
+    /// Charge per block while the block's emission is at least `basic_charge_amount` plus
+    /// `min_miner_reward`
+    pub basic_charge_amount: u64,
+    /// Emission left to the miner when the charge is reduced, and payout per block once
+    /// re-emission starts
+    pub min_miner_reward: u64,
+}
+
+/// Parameters of the emission curve, amounts in nanoergs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmissionSchedule {
+    /// Number of blocks emitting `fixed_rate`, counting from height 1
+    fixed_rate_period: u32,
+    fixed_rate: u64,
+    /// Part of `fixed_rate` going to the treasury while the fixed rate applies
+    founders_initial_reward: u64,
+    /// Number of blocks between emission reductions after the fixed rate period
+    epoch_length: u32,
+    one_epoch_reduction: u64,
+    reemission: Option<ReemissionRules>,
+}
+
+impl EmissionSchedule {
+    /// `fixed_rate` is emitted by the first `fixed_rate_period` blocks, `founders_initial_reward`
+    /// of it going to the treasury. Afterwards the emission is reduced by `one_epoch_reduction`
+    /// every `epoch_length` blocks until it reaches 0
+    pub fn new(
+        fixed_rate_period: u32,
+        fixed_rate: u64,
+        founders_initial_reward: u64,
+        epoch_length: u32,
+        one_epoch_reduction: u64,
+        reemission: Option<ReemissionRules>,
+    ) -> Result<Self, EmissionScheduleError> {
+        if fixed_rate_period == 0 {
+            return Err(EmissionScheduleError::ZeroFixedRatePeriod);
+        }
+        if epoch_length == 0 {
+            return Err(EmissionScheduleError::ZeroEpochLength);
+        }
+        if one_epoch_reduction == 0 {
+            return Err(EmissionScheduleError::ZeroEpochReduction);
+        }
+        if founders_initial_reward > fixed_rate {
+            return Err(EmissionScheduleError::FoundersRewardAboveFixedRate);
+        }
+        Ok(Self {
+            fixed_rate_period,
+            fixed_rate,
+            founders_initial_reward,
+            epoch_length,
+            one_epoch_reduction,
+            reemission,
+        })
+    }
+
+    pub fn mainnet() -> Self {
+        Self {
+            fixed_rate_period: 525_600,
+            fixed_rate: 75 * NANO_ERGS_PER_ERG,
+            founders_initial_reward: 75 * NANO_ERGS_PER_ERG / 10,
+            epoch_length: 64_800,
+            one_epoch_reduction: 3 * NANO_ERGS_PER_ERG,
+            reemission: Some(ReemissionRules {
+                activation_height: 777_217,
+                start_height: 2_080_800,
+                basic_charge_amount: 12 * NANO_ERGS_PER_ERG,
+                min_miner_reward: 3 * NANO_ERGS_PER_ERG,
+            }),
+        }
+    }
+
+    pub fn fixed_rate_period(&self) -> u32 {
+        self.fixed_rate_period
+    }
+
+    pub fn fixed_rate(&self) -> u64 {
+        self.fixed_rate
+    }
+
+    pub fn founders_initial_reward(&self) -> u64 {
+        self.founders_initial_reward
+    }
+
+    pub fn epoch_length(&self) -> u32 {
+        self.epoch_length
+    }
+
+    pub fn one_epoch_reduction(&self) -> u64 {
+        self.one_epoch_reduction
+    }
+
+    pub fn reemission(&self) -> Option<&ReemissionRules> {
+        self.reemission.as_ref()
+    }
+
+    /// First height after the two epochs in which the treasury still receives part of the
+    /// emission
+    fn founders_end_height(&self) -> u64 {
+        self.fixed_rate_period as u64 + 2 * self.epoch_length as u64
+    }
+
+    /// Number of epochs after the fixed rate period before emission stops
+    fn num_epochs(&self) -> u64 {
+        self.fixed_rate.div_ceil(self.one_epoch_reduction)
+    }
+
+    /// Ranges of heights with the same emission, as (first height, last height, emission)
+    fn segments(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        let fixed_rate_period = self.fixed_rate_period as u64;
+        let epoch_length = self.epoch_length as u64;
+        std::iter::once((1, fixed_rate_period - 1, self.fixed_rate)).chain(
+            (1..self.num_epochs()).map(move |epoch| {
+                let first_height = fixed_rate_period + (epoch - 1) * epoch_length;
+                (
+                    first_height,
+                    first_height + epoch_length - 1,
+                    self.fixed_rate - self.one_epoch_reduction * epoch,
+                )
+            }),
+        )
+    }
+
+    /// Sums `amount(emission)` over heights `from..=to`
+    fn sum_over_heights(&self, from: u64, to: u64, amount: impl Fn(u64) -> u64) -> u64 {
+        self.segments()
+            .map(|(first_height, last_height, emission)| {
+                let first_height = first_height.max(from);
+                let last_height = last_height.min(to);
+                if first_height > last_height {
+                    0
+                } else {
+                    (last_height - first_height + 1) * amount(emission)
+                }
+            })
+            .sum()
+    }
+
+    /// Coins emitted by the block at `height`, including the treasury's part
+    pub fn emission_at(&self, height: u32) -> u64 {
+        if height < self.fixed_rate_period {
+            self.fixed_rate
+        } else {
+            let epoch = 1 + ((height - self.fixed_rate_period) / self.epoch_length) as u64;
+            self.fixed_rate
+                .saturating_sub(self.one_epoch_reduction.saturating_mul(epoch))
+        }
+    }
+
+    /// Part of the emission at `height` going to the miner, before the re-emission charge
+    pub fn miner_reward_at(&self, height: u32) -> u64 {
+        if (height as u64) < self.founders_end_height() {
+            self.fixed_rate - self.founders_initial_reward
+        } else {
+            self.emission_at(height)
+        }
+    }
+
+    /// Part of the emission at `height` going to the treasury
+    pub fn founders_reward_at(&self, height: u32) -> u64 {
+        if height < self.fixed_rate_period {
+            self.founders_initial_reward
+        } else if (height as u64) < self.founders_end_height() {
+            self.emission_at(height)
+                .saturating_sub(self.miner_reward_at(height))
+        } else {
+            0
+        }
+    }
+
+    /// Coins emitted up to and including `height`
+    pub fn issued_at(&self, height: u32) -> u64 {
+        self.sum_over_heights(1, height as u64, |emission| emission)
+    }
+
+    /// Coins emitted over the whole schedule
+    pub fn coins_total(&self) -> u64 {
+        self.sum_over_heights(1, u64::MAX, |emission| emission)
+    }
+
+    /// Coins left in the emission contract after `height`
+    pub fn remaining_at(&self, height: u32) -> u64 {
+        self.coins_total() - self.issued_at(height)
+    }
+
+    fn reemission_charge(rules: &ReemissionRules, emission: u64) -> u64 {
+        if emission
+            >= rules
+                .basic_charge_amount
+                .saturating_add(rules.min_miner_reward)
+        {
+            rules.basic_charge_amount
+        } else {
+            emission.saturating_sub(rules.min_miner_reward)
+        }
+    }
+
+    /// Part of the emission at `height` the miner sends to the re-emission contract
+    pub fn reemission_charge_at(&self, height: u32) -> u64 {
+        match &self.reemission {
+            Some(rules) if height >= rules.activation_height => {
+                Self::reemission_charge(rules, self.emission_at(height))
+            }
+            _ => 0,
+        }
+    }
+
+    /// Coins sent to the re-emission contract up to and including `height`
+    pub fn reemission_charged_at(&self, height: u32) -> u64 {
+        match &self.reemission {
+            Some(rules) => {
+                self.sum_over_heights(rules.activation_height as u64, height as u64, |emission| {
+                    Self::reemission_charge(rules, emission)
+                })
+            }
+            None => 0,
+        }
+    }
+
+    /// Coins paid out by the re-emission contract up to and including `height`.
+    /// Payouts stop once every charged coin is paid out
+    pub fn reemitted_at(&self, height: u32) -> u64 {
+        match &self.reemission {
+            Some(rules) if height >= rules.start_height => {
+                let blocks = (height - rules.start_height + 1) as u64;
+                blocks
+                    .saturating_mul(rules.min_miner_reward)
+                    .min(self.reemission_charged_at(height))
+            }
+            _ => 0,
+        }
+    }
+
+    /// Coins in circulation after the block at `height`: emitted coins, without those waiting in
+    /// the re-emission contract
+    pub fn circulating_supply_at(&self, height: u32) -> u64 {
+        self.issued_at(height) - self.reemission_charged_at(height) + self.reemitted_at(height)
+    }
+
+    /// Emission state at `height`, as returned by the node's /emission/at/{height} endpoint
+    pub fn info_at(&self, height: u32) -> EmissionInfo {
+        EmissionInfo {
+            miner_reward: self.miner_reward_at(height) - self.reemission_charge_at(height),
+            total_coins_issued: self.issued_at(height),
+            total_remain_coins: self.remaining_at(height),
+            reemitted: self.reemission_charge_at(height),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmissionScheduleError {
+    #[error("Fixed rate period must last at least one block")]
+    ZeroFixedRatePeriod,
+
+    #[error("Epochs must last at least one block")]
+    ZeroEpochLength,
+
+    #[error("Emission must be reduced every epoch")]
+    ZeroEpochReduction,
+
+    #[error("Founders reward can't exceed the fixed rate")]
+    FoundersRewardAboveFixedRate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mainnet_totals() {
+        let schedule = EmissionSchedule::mainnet();
+        assert_eq!(schedule.coins_total(), 97_739_925 * NANO_ERGS_PER_ERG);
+        assert_eq!(schedule.issued_at(525_599), 39_419_925 * NANO_ERGS_PER_ERG);
+        assert_eq!(
+            schedule.remaining_at(525_599),
+            (97_739_925 - 39_419_925) * NANO_ERGS_PER_ERG
+        );
+    }
+
+    #[test]
+    fn mainnet_rewards() {
+        let schedule = EmissionSchedule::mainnet();
+        assert_eq!(schedule.emission_at(1), 75 * NANO_ERGS_PER_ERG);
+        assert_eq!(schedule.miner_reward_at(1), 675 * NANO_ERGS_PER_ERG / 10);
+        assert_eq!(schedule.founders_reward_at(1), 75 * NANO_ERGS_PER_ERG / 10);
+        assert_eq!(schedule.emission_at(525_600), 72 * NANO_ERGS_PER_ERG);
+        assert_eq!(
+            schedule.founders_reward_at(525_600),
+            45 * NANO_ERGS_PER_ERG / 10
+        );
+        assert_eq!(schedule.founders_reward_at(655_200), 0);
+    }
+
+    #[test]
+    fn mainnet_reemission_charge() {
+        let schedule = EmissionSchedule::mainnet();
+        assert_eq!(schedule.reemission_charge_at(777_216), 0);
+        assert_eq!(
+            schedule.reemission_charge_at(777_217),
+            12 * NANO_ERGS_PER_ERG
+        );
+        assert_eq!(
+            schedule.reemission_charged_at(777_217),
+            12 * NANO_ERGS_PER_ERG
+        );
+        assert_eq!(schedule.reemitted_at(777_217), 0);
+        assert_eq!(
+            schedule.circulating_supply_at(777_217),
+            schedule.issued_at(777_217) - 12 * NANO_ERGS_PER_ERG
+        );
+    }
+
+    #[test]
+    fn mainnet_info_matches_node() {
+        let schedule = EmissionSchedule::mainnet();
+        // /emission/at/1 on mainnet
+        let at_1: EmissionInfo = serde_json::from_str(
+            r#"{
+                "minerReward": 67500000000,
+                "totalCoinsIssued": 75000000000,
+                "totalRemainCoins": 97739850000000000,
+                "reemitted": 0
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(schedule.info_at(1), at_1);
+        // /emission/at/777217 on mainnet, the first block charged for re-emission
+        let at_777_217: EmissionInfo = serde_json::from_str(
+            r#"{
+                "minerReward": 51000000000,
+                "totalCoinsIssued": 56438259000000000,
+                "totalRemainCoins": 41301666000000000,
+                "reemitted": 12000000000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(schedule.info_at(777_217), at_777_217);
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let new =
+            |fixed_rate_period, founders_initial_reward, epoch_length, one_epoch_reduction| {
+                EmissionSchedule::new(
+                    fixed_rate_period,
+                    75,
+                    founders_initial_reward,
+                    epoch_length,
+                    one_epoch_reduction,
+                    None,
+                )
+            };
+        assert_eq!(
+            new(0, 7, 10, 3),
+            Err(EmissionScheduleError::ZeroFixedRatePeriod)
+        );
+        assert_eq!(
+            new(10, 7, 0, 3),
+            Err(EmissionScheduleError::ZeroEpochLength)
+        );
+        assert_eq!(
+            new(10, 7, 10, 0),
+            Err(EmissionScheduleError::ZeroEpochReduction)
+        );
+        assert_eq!(
+            new(10, 76, 10, 3),
+            Err(EmissionScheduleError::FoundersRewardAboveFixedRate)
+        );
+        // the shortest valid schedule reduces the emission from the first block on
+        let schedule = new(1, 7, 1, 3).unwrap();
+        assert_eq!(schedule.emission_at(1), 72);
+        assert_eq!(schedule.founders_reward_at(1), 72 - (75 - 7));
+        assert_eq!(
+            schedule.coins_total(),
+            (1..25u64).map(|epoch| 75 - 3 * epoch).sum::<u64>()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::node::{process_response, NodeError};
+use ergo_lib::{chain::parameters::Parameters, ergo_chain_types::BlockId};
 use reqwest::{Client, Url};
 use serde::Deserialize;
 
@@ -18,9 +19,14 @@ impl<'a> RootEndpoint<'a> {
 #[serde(rename_all = "camelCase")]
 pub struct InfoResponse {
     pub network: String,
+    /// Version of the node, which determines the version of its ErgoScript compiler
+    pub app_version: String,
     pub difficulty: u64,
     pub full_height: i32,
     pub headers_height: i32,
+    pub best_header_id: Option<BlockId>,
+    /// Current blockchain parameters, as voted on by miners
+    pub parameters: Parameters,
 }
 
 impl<'a> RootEndpoint<'a> {
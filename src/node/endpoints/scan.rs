@@ -1,3 +1,5 @@
+pub mod rule;
+
 use std::borrow::Cow;
 
 use ergo_lib::{
@@ -8,8 +10,9 @@ use ergo_lib::{
             ergo_box::{ErgoBox, RegisterId},
             token::TokenId,
         },
-        mir::constant::Constant,
+        mir::constant::{Constant, Literal, TryExtractInto},
         serialization::SigmaSerializable,
+        types::stype::SType,
     },
 };
 use reqwest::Client;
@@ -99,16 +102,142 @@ pub enum TrackingRule {
     Or { args: Vec<TrackingRule> },
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+impl TrackingRule {
+    /// Checks whether `ergo_box` would be picked up by a scan using this rule.
+    /// Follows the node's scanning predicate semantics, so rules can be tested locally before
+    /// they are registered. When `register` is `None` the predicate applies to R1 (the box's ErgoTree)
+    pub fn matches(&self, ergo_box: &ErgoBox) -> bool {
+        match self {
+            TrackingRule::Equals { register, value } => match box_register(ergo_box, *register) {
+                Some(reg_value) => match (byte_array(value), byte_array(&reg_value)) {
+                    (Some(expected), Some(found)) => expected == found,
+                    // the node only compares these types and never matches any other
+                    (None, None) => {
+                        matches!(
+                            value.tpe,
+                            SType::SGroupElement | SType::SBoolean | SType::SInt | SType::SLong
+                        ) && reg_value == *value
+                    }
+                    _ => false,
+                },
+                None => false,
+            },
+            TrackingRule::Contains { register, value } => match box_register(ergo_box, *register) {
+                Some(reg_value) => match (&value.v, &reg_value.v) {
+                    (Literal::GroupElement(expected), Literal::GroupElement(found)) => {
+                        expected == found
+                    }
+                    _ => match (byte_array(value), byte_array(&reg_value)) {
+                        (Some(needle), Some(haystack)) => contains_slice(&haystack, &needle),
+                        _ => false,
+                    },
+                },
+                None => false,
+            },
+            TrackingRule::ContainsAsset { asset_id } => ergo_box
+                .tokens
+                .as_ref()
+                .is_some_and(|tokens| tokens.iter().any(|token| token.token_id == *asset_id)),
+            TrackingRule::And { args } => args.iter().all(|rule| rule.matches(ergo_box)),
+            TrackingRule::Or { args } => args.iter().any(|rule| rule.matches(ergo_box)),
+        }
+    }
+}
+
+fn box_register(ergo_box: &ErgoBox, register: Option<RegisterId>) -> Option<Constant> {
+    ergo_box
+        .get_register(register.unwrap_or(RegisterId::R1))
+        .ok()
+        .flatten()
+}
+
+fn byte_array(constant: &Constant) -> Option<Vec<u8>> {
+    match &constant.tpe {
+        SType::SColl(elem_tpe) if **elem_tpe == SType::SByte => {
+            constant.v.clone().try_extract_into::<Vec<u8>>().ok()
+        }
+        _ => None,
+    }
+}
+
+fn contains_slice(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+/// How boxes found by a scan interact with the node's wallet.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletInteraction {
+    /// Boxes are only added to the scan.
+    Off,
+    /// Boxes are added to the scan, and also to the wallet if the wallet can spend them.
+    /// This is the node's default.
+    #[default]
+    Shared,
+    /// Boxes are always added to the wallet as well as to the scan.
+    Forced,
+}
+
+fn default_remove_offchain() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Scan<'a> {
     pub scan_name: Cow<'a, str>,
-    pub wallet_interaction: Cow<'a, str>,
+    #[serde(default)]
+    pub wallet_interaction: WalletInteraction,
     pub tracking_rule: TrackingRule,
+    #[serde(default = "default_remove_offchain")]
     pub remove_offchain: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+impl<'a> Scan<'a> {
+    /// Starts building a scan, using the same defaults the node applies to omitted fields
+    /// (`walletInteraction: shared`, `removeOffchain: true`)
+    pub fn builder(
+        scan_name: impl Into<Cow<'a, str>>,
+        tracking_rule: TrackingRule,
+    ) -> ScanBuilder<'a> {
+        ScanBuilder {
+            scan: Scan {
+                scan_name: scan_name.into(),
+                wallet_interaction: WalletInteraction::default(),
+                tracking_rule,
+                remove_offchain: default_remove_offchain(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanBuilder<'a> {
+    scan: Scan<'a>,
+}
+
+impl<'a> ScanBuilder<'a> {
+    pub fn wallet_interaction(mut self, wallet_interaction: WalletInteraction) -> Self {
+        self.scan.wallet_interaction = wallet_interaction;
+        self
+    }
+
+    /// Whether boxes that were created off-chain (in the mempool) and never made it into a block
+    /// should be removed from the scan
+    pub fn remove_offchain(mut self, remove_offchain: bool) -> Self {
+        self.scan.remove_offchain = remove_offchain;
+        self
+    }
+
+    pub fn build(self) -> Scan<'a> {
+        self.scan
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisteredScan<'a> {
     pub scan_id: u32,
@@ -127,6 +256,21 @@ pub struct ScanQuery {
     pub offset: u32,
 }
 
+impl Default for ScanQuery {
+    fn default() -> Self {
+        // -1 means unlimited
+        // these are the defaults in the api docs
+        Self {
+            min_confirmations: 0,
+            max_confirmations: -1,
+            min_inclusion_height: 0,
+            max_inclusion_height: -1,
+            limit: 500,
+            offset: 0,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct ScanBox {
     #[serde(rename = "confirmationsNum")]
@@ -218,4 +362,241 @@ impl<'a> ScanEndpoint<'a> {
         )
         .await
     }
+
+    pub async fn spent_boxes(
+        &self,
+        scan_id: u32,
+        query: &ScanQuery,
+    ) -> Result<Vec<ScanBox>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["spentBoxes", &format!("{scan_id}")]);
+        process_response(
+            self.client
+                .get(url)
+                .query(query)
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ergo_lib::{
+        ergo_chain_types::{ec_point::generator, Digest32},
+        ergotree_ir::{
+            chain::{
+                address::Address,
+                ergo_box::{
+                    box_value::BoxValue, BoxId, NonMandatoryRegisterId, NonMandatoryRegisters,
+                },
+                token::{Token, TokenAmount},
+            },
+            sigma_protocol::sigma_boolean::ProveDlog,
+        },
+    };
+
+    use super::*;
+
+    fn token_id(byte: u8) -> TokenId {
+        BoxId::from(Digest32::from([byte; 32])).into()
+    }
+
+    fn test_box(registers: Vec<Constant>, tokens: Vec<TokenId>) -> ErgoBox {
+        let ergo_tree = Address::P2Pk(ProveDlog::new(generator())).script().unwrap();
+        let tokens = tokens
+            .into_iter()
+            .map(|token_id| Token {
+                token_id,
+                amount: TokenAmount::try_from(1u64).unwrap(),
+            })
+            .collect::<Vec<_>>();
+        ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            ergo_tree,
+            (!tokens.is_empty()).then(|| tokens.try_into().unwrap()),
+            NonMandatoryRegisters::try_from(registers).unwrap(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn registered_scans_round_trip() {
+        let payload = json!([
+            {
+                "scanId": 11,
+                "scanName": "Assets Tracker",
+                "walletInteraction": "off",
+                "trackingRule": {
+                    "predicate": "containsAsset",
+                    "assetId": "02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cbc7d4bde3e3e"
+                },
+                "removeOffchain": true
+            },
+            {
+                "scanId": 12,
+                "scanName": "Oracle",
+                "walletInteraction": "forced",
+                "trackingRule": {
+                    "predicate": "or",
+                    "args": [
+                        { "predicate": "equals", "register": "R4", "value": "0402" },
+                        { "predicate": "contains", "value": "0e020102" }
+                    ]
+                },
+                "removeOffchain": false
+            }
+        ]);
+        // parsed from text like reqwest does, which register ids are borrowed from
+        let payload_str = payload.to_string();
+        let scans: Vec<RegisteredScan> = serde_json::from_str(&payload_str).unwrap();
+        assert_eq!(scans[0].scan.wallet_interaction, WalletInteraction::Off);
+        assert_eq!(
+            scans[1].scan.tracking_rule,
+            TrackingRule::Or {
+                args: vec![
+                    TrackingRule::Equals {
+                        register: Some(RegisterId::from(NonMandatoryRegisterId::R4)),
+                        value: 1i32.into(),
+                    },
+                    TrackingRule::Contains {
+                        register: None,
+                        value: vec![1u8, 2].into(),
+                    },
+                ]
+            }
+        );
+        assert_eq!(serde_json::to_value(&scans).unwrap(), payload);
+    }
+
+    #[test]
+    fn omitted_fields_use_node_defaults() {
+        let scan: Scan = serde_json::from_value(json!({
+            "scanName": "Assets Tracker",
+            "trackingRule": {
+                "predicate": "containsAsset",
+                "assetId": "02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cbc7d4bde3e3e"
+            }
+        }))
+        .unwrap();
+        assert_eq!(scan.wallet_interaction, WalletInteraction::Shared);
+        assert!(scan.remove_offchain);
+        assert_eq!(
+            Scan::builder("Assets Tracker", scan.tracking_rule.clone()).build(),
+            scan
+        );
+    }
+
+    #[test]
+    fn equals_defaults_to_ergo_tree() {
+        let ergo_box = test_box(vec![], vec![]);
+        let tree_bytes = ergo_box.ergo_tree.sigma_serialize_bytes().unwrap();
+        let rule = TrackingRule::Equals {
+            register: None,
+            value: tree_bytes.clone().into(),
+        };
+        assert!(rule.matches(&ergo_box));
+
+        let rule = TrackingRule::Equals {
+            register: None,
+            value: tree_bytes[1..].to_vec().into(),
+        };
+        assert!(!rule.matches(&ergo_box));
+    }
+
+    #[test]
+    fn equals_compares_register_values() {
+        let ergo_box = test_box(vec![42i32.into()], vec![]);
+        let equals = |value: Constant| TrackingRule::Equals {
+            register: Some(RegisterId::from(NonMandatoryRegisterId::R4)),
+            value,
+        };
+        assert!(equals(42i32.into()).matches(&ergo_box));
+        assert!(!equals(43i32.into()).matches(&ergo_box));
+        assert!(!equals(vec![42u8].into()).matches(&ergo_box));
+        assert!(!TrackingRule::Equals {
+            register: Some(RegisterId::from(NonMandatoryRegisterId::R5)),
+            value: 42i32.into(),
+        }
+        .matches(&ergo_box));
+    }
+
+    #[test]
+    fn equals_ignores_types_the_node_does_not_compare() {
+        let supported: Vec<Constant> = vec![true.into(), 42i64.into(), generator().into()];
+        let unsupported: Vec<Constant> =
+            vec![42i16.into(), vec![1i32, 2].into(), (1i32, 2i32).into()];
+        let ergo_box = test_box(
+            supported.iter().chain(&unsupported).cloned().collect(),
+            vec![],
+        );
+        for (index, value) in supported.into_iter().chain(unsupported).enumerate() {
+            let rule = TrackingRule::Equals {
+                register: Some(RegisterId::try_from(4 + index as i8).unwrap()),
+                value,
+            };
+            assert_eq!(rule.matches(&ergo_box), index < 3, "{rule:?}");
+        }
+    }
+
+    #[test]
+    fn contains_finds_bytes_and_group_elements() {
+        let ergo_box = test_box(vec![vec![1u8, 2, 3, 4].into(), generator().into()], vec![]);
+        let contains = |register, value: Constant| TrackingRule::Contains {
+            register: Some(register),
+            value,
+        };
+        assert!(contains(
+            RegisterId::from(NonMandatoryRegisterId::R4),
+            vec![2u8, 3].into()
+        )
+        .matches(&ergo_box));
+        assert!(contains(
+            RegisterId::from(NonMandatoryRegisterId::R4),
+            Vec::<u8>::new().into()
+        )
+        .matches(&ergo_box));
+        assert!(!contains(
+            RegisterId::from(NonMandatoryRegisterId::R4),
+            vec![3u8, 2].into()
+        )
+        .matches(&ergo_box));
+        assert!(contains(
+            RegisterId::from(NonMandatoryRegisterId::R5),
+            generator().into()
+        )
+        .matches(&ergo_box));
+        assert!(!contains(
+            RegisterId::from(NonMandatoryRegisterId::R5),
+            vec![2u8].into()
+        )
+        .matches(&ergo_box));
+    }
+
+    #[test]
+    fn contains_asset_and_combinators() {
+        let ergo_box = test_box(vec![], vec![token_id(1)]);
+        let has = |byte| TrackingRule::ContainsAsset {
+            asset_id: token_id(byte),
+        };
+        assert!(has(1).matches(&ergo_box));
+        assert!(!has(2).matches(&test_box(vec![], vec![])));
+        assert!(!TrackingRule::And {
+            args: vec![has(1), has(2)]
+        }
+        .matches(&ergo_box));
+        assert!(TrackingRule::Or {
+            args: vec![has(2), has(1)]
+        }
+        .matches(&ergo_box));
+        assert!(TrackingRule::And { args: vec![] }.matches(&ergo_box));
+        assert!(!TrackingRule::Or { args: vec![] }.matches(&ergo_box));
+    }
 }
@@ -0,0 +1,181 @@
+//! Helpers for building [`TrackingRule`]s without spelling out the enum by hand, e.g.
+//! `rule::contains_asset(token_id).and(rule::register_eq(R4, value))`
+use ergo_lib::ergotree_ir::{
+    chain::{
+        address::Address,
+        ergo_box::{NonMandatoryRegisterId, RegisterId},
+        token::TokenId,
+    },
+    ergo_tree::{ErgoTree, ErgoTreeError},
+    mir::constant::Constant,
+    serialization::{SigmaSerializable, SigmaSerializationError},
+};
+
+use super::TrackingRule;
+use crate::node::NodeError;
+
+/// Matches boxes whose `register` is equal to `value`
+pub fn register_eq(register: NonMandatoryRegisterId, value: impl Into<Constant>) -> TrackingRule {
+    TrackingRule::Equals {
+        register: Some(register.into()),
+        value: value.into(),
+    }
+}
+
+/// Matches boxes whose `register` holds a byte array containing `value`
+pub fn register_contains(
+    register: NonMandatoryRegisterId,
+    value: impl Into<Constant>,
+) -> TrackingRule {
+    TrackingRule::Contains {
+        register: Some(register.into()),
+        value: value.into(),
+    }
+}
+
+/// Matches boxes holding any amount of the token `token_id`
+pub fn contains_asset(token_id: TokenId) -> TrackingRule {
+    TrackingRule::ContainsAsset { asset_id: token_id }
+}
+
+/// Matches boxes guarded by exactly `ergo_tree`
+pub fn ergo_tree(ergo_tree: &ErgoTree) -> Result<TrackingRule, SigmaSerializationError> {
+    Ok(TrackingRule::Equals {
+        register: Some(RegisterId::R1),
+        value: ergo_tree.sigma_serialize_bytes()?.into(),
+    })
+}
+
+/// Matches boxes that can be spent by `address`, e.g. boxes sent to a P2PK address
+pub fn address(address: &Address) -> Result<TrackingRule, NodeError> {
+    Ok(ergo_tree(&address.script()?)?)
+}
+
+/// Matches boxes guarded by any instance of the contract template of `ergo_tree`,
+/// regardless of the values of its segregated constants.
+///
+/// The node can't hash registers while scanning, so instead of the template hash this matches the
+/// template bytes themselves, which appear verbatim in R1 after the header and constants
+pub fn ergo_tree_template(ergo_tree: &ErgoTree) -> Result<TrackingRule, ErgoTreeError> {
+    Ok(TrackingRule::Contains {
+        register: Some(RegisterId::R1),
+        value: ergo_tree.template_bytes()?.into(),
+    })
+}
+
+/// Matches boxes matching every rule in `rules`
+pub fn all(rules: impl IntoIterator<Item = TrackingRule>) -> TrackingRule {
+    TrackingRule::And {
+        args: rules.into_iter().collect(),
+    }
+}
+
+/// Matches boxes matching at least one rule in `rules`
+pub fn any(rules: impl IntoIterator<Item = TrackingRule>) -> TrackingRule {
+    TrackingRule::Or {
+        args: rules.into_iter().collect(),
+    }
+}
+
+impl TrackingRule {
+    /// Combines two rules, matching boxes that match both.
+    /// Chained calls are flattened into a single `And`
+    pub fn and(self, other: TrackingRule) -> TrackingRule {
+        match self {
+            TrackingRule::And { mut args } => {
+                args.push(other);
+                TrackingRule::And { args }
+            }
+            rule => all([rule, other]),
+        }
+    }
+
+    /// Combines two rules, matching boxes that match either.
+    /// Chained calls are flattened into a single `Or`
+    pub fn or(self, other: TrackingRule) -> TrackingRule {
+        match self {
+            TrackingRule::Or { mut args } => {
+                args.push(other);
+                TrackingRule::Or { args }
+            }
+            rule => any([rule, other]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ergo_lib::{
+        chain::transaction::TxId,
+        ergo_chain_types::ec_point::{generator, inverse},
+        ergotree_ir::{
+            chain::ergo_box::{box_value::BoxValue, ErgoBox, NonMandatoryRegisters},
+            ergo_tree::ErgoTreeHeader,
+            mir::expr::Expr,
+            sigma_protocol::sigma_boolean::ProveDlog,
+        },
+    };
+
+    use super::*;
+
+    fn test_box(ergo_tree: ErgoTree) -> ErgoBox {
+        ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            ergo_tree,
+            None,
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn rules(n: i32) -> Vec<TrackingRule> {
+        (0..n)
+            .map(|i| register_eq(NonMandatoryRegisterId::R4, i))
+            .collect()
+    }
+
+    #[test]
+    fn chained_rules_are_flattened() {
+        let [a, b, c] = <[_; 3]>::try_from(rules(3)).unwrap();
+        assert_eq!(a.clone().and(b.clone()).and(c.clone()), all(rules(3)));
+        assert_eq!(a.clone().or(b.clone()).or(c.clone()), any(rules(3)));
+        // a different combinator starts a new level
+        assert_eq!(
+            a.clone().and(b.clone()).or(c.clone()),
+            any([all([a, b]), c])
+        );
+    }
+
+    #[test]
+    fn template_matches_trees_with_other_constants() {
+        let tree = |pk| {
+            let expr = Expr::Const(ProveDlog::new(pk).into());
+            ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap()
+        };
+        let (tree_a, tree_b) = (tree(generator()), tree(inverse(&generator())));
+        assert_ne!(tree_a, tree_b);
+
+        let template = ergo_tree_template(&tree_a).unwrap();
+        assert!(template.matches(&test_box(tree_a.clone())));
+        assert!(template.matches(&test_box(tree_b.clone())));
+
+        let exact = ergo_tree(&tree_a).unwrap();
+        assert!(exact.matches(&test_box(tree_a)));
+        assert!(!exact.matches(&test_box(tree_b)));
+    }
+
+    #[test]
+    fn template_does_not_match_other_contracts() {
+        let p2pk = Address::P2Pk(ProveDlog::new(generator()));
+        let expr = Expr::Const(true.into());
+        let other = ErgoTree::new(ErgoTreeHeader::v0(true), &expr).unwrap();
+        let template = ergo_tree_template(&other).unwrap();
+        assert!(!template.matches(&test_box(p2pk.script().unwrap())));
+        assert!(address(&p2pk)
+            .unwrap()
+            .matches(&test_box(p2pk.script().unwrap())));
+    }
+}
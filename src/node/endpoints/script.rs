@@ -1,6 +1,23 @@
+use std::{collections::BTreeMap, fmt};
+
 use crate::node::{process_response, NodeError};
-use reqwest::{Client, Url};
-use serde::Deserialize;
+use ergo_lib::{
+    chain::{ergo_state_context::ErgoStateContext, transaction::ergo_transaction::ErgoTransaction},
+    ergo_chain_types::{
+        ec_point, ADDigest, Base16DecodedBytes, Base16EncodedBytes, BlockId, Digest32, EcPoint,
+        Header, PreHeader, Votes,
+    },
+    ergotree_interpreter::sigma_protocol::prover::ContextExtension,
+    ergotree_ir::{
+        chain::{address::NetworkAddress, ergo_box::ErgoBox},
+        ergo_tree::ErgoTree,
+        mir::{avl_tree_data::AvlTreeFlags, constant::Constant},
+        serialization::{SigmaSerializable, SigmaSerializationError},
+        sigma_protocol::sigma_boolean::SigmaBoolean,
+    },
+};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 #[derive(Debug, Clone)]
@@ -20,40 +37,377 @@ impl<'a> ScriptEndpoint<'a> {
 
 #[derive(Debug, Deserialize)]
 struct AddressToTreeResponse {
-    tree: String,
+    tree: Base16DecodedBytes,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddressToBytesResponse {
+    bytes: Base16DecodedBytes,
 }
 
 impl<'a> ScriptEndpoint<'a> {
-    pub async fn address_to_tree(&self, network_address: &str) -> Result<String, NodeError> {
+    /// Get the ErgoTree guarding boxes sent to `address` (/script/addressToTree endpoint)
+    pub async fn address_to_tree(&self, address: &NetworkAddress) -> Result<ErgoTree, NodeError> {
         let mut url = self.url.clone();
         url.path_segments_mut()
             .map_err(|_| NodeError::BaseUrl)?
             .push("addressToTree")
-            .push(network_address);
-        Ok(process_response::<AddressToTreeResponse>(
+            .push(&address.to_base58());
+        let tree = process_response::<AddressToTreeResponse>(
             self.client.get(url).send().await.map_err(NodeError::Http)?,
         )
         .await?
-        .tree)
+        .tree;
+        Ok(ErgoTree::sigma_parse_bytes(&tree.0)?)
+    }
+
+    /// Get the ErgoTree of `address` as a `Coll[Byte]` constant, the form it's compared with in
+    /// registers and scan rules (/script/addressToBytes endpoint)
+    pub async fn address_to_bytes(&self, address: &NetworkAddress) -> Result<Constant, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("addressToBytes")
+            .push(&address.to_base58());
+        let bytes = process_response::<AddressToBytesResponse>(
+            self.client.get(url).send().await.map_err(NodeError::Http)?,
+        )
+        .await?
+        .bytes;
+        Ok(Constant::sigma_parse_bytes(&bytes.0)?)
+    }
+}
+
+/// ErgoScript compilation error reported by the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    /// 1-based line of the error in the source, if the compiler reported its position
+    pub line: Option<u32>,
+    /// 1-based column of the error in the source, if the compiler reported its position
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl CompileError {
+    /// Parses the error detail returned by the node. The compiler reports a position as
+    /// `line {line}: {source line}`, followed by a line with a caret under the failing column and
+    /// then the message
+    pub fn from_node_detail(detail: &str) -> Self {
+        let lines = detail.lines().collect::<Vec<_>>();
+        for (idx, line) in lines.iter().enumerate() {
+            let Some((number, _)) = line
+                .strip_prefix("line ")
+                .and_then(|rest| rest.split_once(": "))
+            else {
+                continue;
+            };
+            let Ok(line_number) = number.parse::<u32>() else {
+                continue;
+            };
+            let prefix_len = "line ".len() + number.len() + ": ".len();
+            let Some(caret) = lines
+                .get(idx + 1)
+                .and_then(|caret_line| caret_line.find('^'))
+                .filter(|caret| *caret >= prefix_len)
+            else {
+                continue;
+            };
+            let message = match lines[idx + 2..].join("\n").trim() {
+                "" => lines[..idx].join("\n").trim().to_string(),
+                message => message.to_string(),
+            };
+            return Self {
+                line: Some(line_number),
+                column: Some((caret - prefix_len + 1) as u32),
+                message,
+            };
+        }
+        Self {
+            line: None,
+            column: None,
+            message: detail.trim().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "line {line}, column {column}: {}", self.message)
+            }
+            _ => write!(f, "{}", self.message),
+        }
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct P2sAddressResponse {
-    address: String,
+struct CompileResponse {
+    address: NetworkAddress,
 }
 
 impl<'a> ScriptEndpoint<'a> {
     /// Compiles the provided ErgoScript source code to a network encoded address.
-    pub async fn p2s_address(&self, source: &str) -> Result<String, NodeError> {
+    /// Compilation failures are returned as [`NodeError::Compile`]
+    pub async fn p2s_address(&self, source: &str) -> Result<NetworkAddress, NodeError> {
+        self.compile("p2sAddress", json!({ "source": source }))
+            .await
+    }
+
+    /// Compiles the provided ErgoScript source code to a network encoded address with an ErgoTree
+    /// of version `tree_version`, see [`Self::p2s_address`]
+    pub async fn p2s_address_with_tree_version(
+        &self,
+        source: &str,
+        tree_version: u8,
+    ) -> Result<NetworkAddress, NodeError> {
+        self.compile(
+            "p2sAddress",
+            json!({ "source": source, "treeVersion": tree_version }),
+        )
+        .await
+    }
+
+    /// Compiles the provided ErgoScript source code to a pay-to-script-hash address, which
+    /// commits to the script's hash instead of containing the script.
+    /// Compilation failures are returned as [`NodeError::Compile`]
+    pub async fn p2sh_address(&self, source: &str) -> Result<NetworkAddress, NodeError> {
+        self.compile("p2shAddress", json!({ "source": source }))
+            .await
+    }
+
+    async fn compile(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<NetworkAddress, NodeError> {
         let mut url = self.url.clone();
         url.path_segments_mut()
             .map_err(|_| NodeError::BaseUrl)?
-            .push("p2sAddress");
+            .push(path);
+        let response = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(NodeError::Http)?;
+        // the node reports compilation errors as 400, other failures (e.g. a wrong API key) aren't
+        // about the script
+        let is_bad_request = response.status() == StatusCode::BAD_REQUEST;
+        Ok(process_response::<CompileResponse>(response)
+            .await
+            .map_err(|e| match e {
+                NodeError::BadRequest(detail) if is_bad_request => {
+                    NodeError::Compile(CompileError::from_node_detail(&detail))
+                }
+                e => e,
+            })?
+            .address)
+    }
+}
+
+/// AVL tree in a [`ScriptContext`]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextAvlTree {
+    pub digest: ADDigest,
+    pub tree_flags: u8,
+    pub key_length: u32,
+    pub value_length: Option<u32>,
+}
+
+impl ContextAvlTree {
+    /// The UTXO set's tree with root `state_root`, keyed by box id
+    pub fn utxo_set(state_root: ADDigest) -> Self {
+        Self {
+            digest: state_root,
+            tree_flags: AvlTreeFlags::new(true, true, true).serialize(),
+            key_length: 32,
+            value_length: None,
+        }
+    }
+}
+
+/// Block header in a [`ScriptContext`], as scripts see it through `CONTEXT.headers`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextHeader {
+    pub id: BlockId,
+    pub version: u8,
+    pub parent_id: BlockId,
+    pub ad_proofs_root: Digest32,
+    pub state_root: ContextAvlTree,
+    pub transactions_root: Digest32,
+    pub timestamp: u64,
+    pub n_bits: u64,
+    pub height: u32,
+    pub extension_root: Digest32,
+    pub miner_pk: EcPoint,
+    pub pow_onetime_pk: EcPoint,
+    pub pow_nonce: Base16EncodedBytes,
+    /// Number, or string if it doesn't fit into a `u64`
+    pub pow_distance: serde_json::Value,
+    pub votes: Votes,
+}
+
+impl From<&Header> for ContextHeader {
+    fn from(header: &Header) -> Self {
+        let solution = &header.autolykos_solution;
+        // Autolykos v2 solutions don't have a one-time public key and a distance, scripts see
+        // the group generator and zero instead
+        let pow_distance = solution
+            .pow_distance
+            .as_ref()
+            .map_or_else(|| "0".to_string(), |distance| distance.to_string());
+        Self {
+            id: header.id,
+            version: header.version,
+            parent_id: header.parent_id,
+            ad_proofs_root: header.ad_proofs_root,
+            state_root: ContextAvlTree::utxo_set(header.state_root),
+            transactions_root: header.transaction_root,
+            timestamp: header.timestamp,
+            n_bits: header.n_bits,
+            height: header.height,
+            extension_root: header.extension_root,
+            miner_pk: (*solution.miner_pk).clone(),
+            pow_onetime_pk: solution
+                .pow_onetime_pk
+                .as_deref()
+                .cloned()
+                .unwrap_or_else(ec_point::generator),
+            pow_nonce: Base16EncodedBytes::new(&solution.nonce),
+            pow_distance: match pow_distance.parse::<u64>() {
+                Ok(distance) => distance.into(),
+                Err(_) => pow_distance.into(),
+            },
+            votes: header.votes.clone(),
+        }
+    }
+}
+
+/// Pre-header in a [`ScriptContext`], as scripts see it through `CONTEXT.preHeader`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextPreHeader {
+    pub version: u8,
+    pub parent_id: BlockId,
+    pub timestamp: u64,
+    pub n_bits: u64,
+    pub height: u32,
+    pub miner_pk: EcPoint,
+    pub votes: Votes,
+}
+
+impl From<&PreHeader> for ContextPreHeader {
+    fn from(pre_header: &PreHeader) -> Self {
+        Self {
+            version: pre_header.version,
+            parent_id: pre_header.parent_id,
+            timestamp: pre_header.timestamp,
+            n_bits: pre_header.n_bits,
+            height: pre_header.height,
+            miner_pk: (*pre_header.miner_pk).clone(),
+            votes: pre_header.votes.clone(),
+        }
+    }
+}
+
+/// Context a script is reduced in by [`ScriptEndpoint::execute_with_context`].
+/// Serializes to the node's ErgoLikeContext format
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptContext<T: Serialize> {
+    pub last_block_utxo_root: ContextAvlTree,
+    /// Last block headers, newest first
+    pub headers: Vec<ContextHeader>,
+    pub pre_header: ContextPreHeader,
+    pub data_boxes: Vec<ErgoBox>,
+    pub boxes_to_spend: Vec<ErgoBox>,
+    /// Signed or unsigned transaction spending `boxes_to_spend`
+    pub spending_transaction: T,
+    /// Index of the input the script guards in `boxes_to_spend`
+    pub self_index: usize,
+    /// Context extension of the input at `self_index`, serialized constants by variable id
+    pub extension: BTreeMap<u8, Base16EncodedBytes>,
+    /// Base16 encoded serialized validation settings, `00` for the default rules
+    pub validation_settings: String,
+    pub cost_limit: u64,
+    pub init_cost: u64,
+    pub script_version: u8,
+}
+
+impl<T: ErgoTransaction + Serialize> ScriptContext<T> {
+    /// Context of input `self_index` of `spending_tx`, with chain state and cost limit taken from
+    /// `state_context`. `boxes_to_spend` and `data_boxes` are in the same order as the
+    /// transaction's inputs and data inputs
+    pub fn new(
+        spending_tx: T,
+        boxes_to_spend: Vec<ErgoBox>,
+        data_boxes: Vec<ErgoBox>,
+        state_context: &ErgoStateContext,
+        self_index: usize,
+    ) -> Result<Self, SigmaSerializationError> {
+        let extension = spending_tx
+            .context_extension(self_index)
+            .unwrap_or_else(ContextExtension::empty)
+            .values
+            .into_iter()
+            .map(|(id, constant)| {
+                Ok((
+                    id,
+                    Base16EncodedBytes::new(&constant.sigma_serialize_bytes()?),
+                ))
+            })
+            .collect::<Result<_, SigmaSerializationError>>()?;
+        Ok(Self {
+            last_block_utxo_root: ContextAvlTree::utxo_set(state_context.headers[0].state_root),
+            headers: state_context
+                .headers
+                .iter()
+                .map(ContextHeader::from)
+                .collect(),
+            pre_header: ContextPreHeader::from(&state_context.pre_header),
+            data_boxes,
+            boxes_to_spend,
+            spending_transaction: spending_tx,
+            self_index,
+            extension,
+            validation_settings: "00".to_string(),
+            cost_limit: state_context.parameters.max_block_cost() as u64,
+            init_cost: 0,
+            // scripts are evaluated with the version activated by the current block version
+            script_version: (state_context.parameters.block_version() as u8).saturating_sub(1),
+        })
+    }
+}
+
+/// Result of [`ScriptEndpoint::execute_with_context`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptExecution {
+    /// Sigma proposition the script reduced to, which a proof of the input has to satisfy
+    pub value: SigmaBoolean,
+    pub cost: u64,
+}
+
+impl<'a> ScriptEndpoint<'a> {
+    /// Compiles `source` and reduces it in `context` (/script/executeWithContext endpoint)
+    pub async fn execute_with_context<T: Serialize>(
+        &self,
+        source: &str,
+        context: &ScriptContext<T>,
+    ) -> Result<ScriptExecution, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("executeWithContext");
         let body = json!({
-            "source": source
+            "script": source,
+            "namedConstants": {},
+            "context": context,
         });
-        Ok(process_response::<P2sAddressResponse>(
+        process_response(
             self.client
                 .post(url)
                 .json(&body)
@@ -61,7 +415,62 @@ impl<'a> ScriptEndpoint<'a> {
                 .await
                 .map_err(NodeError::Http)?,
         )
-        .await?
-        .address)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_error_with_position() {
+        let detail = "line 1: sigmaProp(HEIGHT > x)\n\
+                      \x20                          ^\n\
+                      Cannot assign type for variable 'x' because it is not found in env";
+        assert_eq!(
+            CompileError::from_node_detail(detail),
+            CompileError {
+                line: Some(1),
+                column: Some(20),
+                message: "Cannot assign type for variable 'x' because it is not found in env"
+                    .to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn compile_error_message_before_position() {
+        let detail = "Unexpected token\nline 12: val a = \n                 ^";
+        let error = CompileError::from_node_detail(detail);
+        assert_eq!(error.line, Some(12));
+        assert_eq!(error.column, Some(9));
+        assert_eq!(error.message, "Unexpected token");
+    }
+
+    #[test]
+    fn compile_error_without_position() {
+        for detail in [
+            "  Cannot compile\n",
+            "line x: not a number\n^\nCannot compile",
+            "line 3: caret missing\nCannot compile",
+            "line 3: caret inside the prefix\n ^\nCannot compile",
+        ] {
+            let error = CompileError::from_node_detail(detail);
+            assert_eq!((error.line, error.column), (None, None), "{detail}");
+            assert_eq!(error.message, detail.trim());
+        }
+    }
+
+    #[test]
+    fn compile_error_display() {
+        let mut error = CompileError {
+            line: Some(2),
+            column: Some(5),
+            message: "Unexpected token".to_string(),
+        };
+        assert_eq!(error.to_string(), "line 2, column 5: Unexpected token");
+        error.column = None;
+        assert_eq!(error.to_string(), "Unexpected token");
     }
 }
@@ -1,6 +1,18 @@
+use super::NodePaginationQuery;
 use crate::node::{process_response, NodeError};
-use ergo_lib::chain::transaction::Transaction;
-use reqwest::{Client, Url};
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use reqwest::{Client, StatusCode, Url};
+use serde::Deserialize;
+
+/// Mempool transactions whose expected wait time falls into one bin of
+/// [`TransactionsEndpoint::pool_histogram`]
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistogramBin {
+    pub n_txns: u32,
+    /// Sum of the fees of the transactions in the bin, in nanoergs
+    pub total_fee: u64,
+}
 
 #[derive(Debug, Clone)]
 pub struct TransactionsEndpoint<'a> {
@@ -31,4 +43,83 @@ impl<'a> TransactionsEndpoint<'a> {
         )
         .await
     }
+
+    /// Get a page of the transactions currently in the mempool (/transactions/unconfirmed)
+    pub async fn unconfirmed(
+        &self,
+        query: &NodePaginationQuery,
+    ) -> Result<Vec<Transaction>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("unconfirmed");
+        process_response(
+            self.client
+                .get(url)
+                .query(query)
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await
+    }
+
+    /// Get an unconfirmed transaction from the mempool, or `None` if it isn't in the mempool
+    /// (/transactions/unconfirmed/byTransactionId/{txId} endpoint)
+    pub async fn unconfirmed_by_id(&self, tx_id: &TxId) -> Result<Option<Transaction>, NodeError> {
+        let mut url = self.url.clone();
+        let tx_id = tx_id.to_string();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["unconfirmed", "byTransactionId", &tx_id]);
+        let response = self.client.get(url).send().await.map_err(NodeError::Http)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        process_response(response).await.map(Some)
+    }
+
+    /// Get the fee in nanoergs the node recommends for a transaction of `tx_size` bytes to be
+    /// confirmed within `wait_time_minutes` (/transactions/getFee endpoint)
+    pub async fn get_fee(&self, wait_time_minutes: u32, tx_size: usize) -> Result<u64, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("getFee");
+        process_response(
+            self.client
+                .get(url)
+                .query(&[
+                    ("waitTime", wait_time_minutes as usize),
+                    ("txSize", tx_size),
+                ])
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await
+    }
+
+    /// Get a histogram of the mempool's transactions by expected wait time
+    /// (/transactions/poolHistogram endpoint). `max_time_ms` is split into `bins` bins of equal
+    /// width, and the last bin also holds every transaction expected to wait longer
+    pub async fn pool_histogram(
+        &self,
+        bins: u32,
+        max_time_ms: u64,
+    ) -> Result<Vec<FeeHistogramBin>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("poolHistogram");
+        process_response(
+            self.client
+                .get(url)
+                .query(&[("bins", bins as u64), ("maxtime", max_time_ms)])
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await
+    }
 }
@@ -1,4 +1,11 @@
-use ergo_lib::{ergo_chain_types::EcPoint, ergotree_ir::chain::address::NetworkAddress};
+pub mod local;
+
+use ergo_lib::{
+    ergo_chain_types::{Base16DecodedBytes, Base16EncodedBytes, Digest32, EcPoint},
+    ergotree_ir::{
+        chain::address::NetworkAddress, ergo_tree::ErgoTree, serialization::SigmaSerializable,
+    },
+};
 use reqwest::Client;
 use serde::Deserialize;
 use url::Url;
@@ -26,6 +33,8 @@ struct RawToAddressResponse {
 }
 
 impl<'a> UtilsEndpoint<'a> {
+    /// Get the P2PK address of `pubkey` on the node's network (/utils/rawToAddress endpoint).
+    /// See [`local::raw_to_address`]
     pub async fn raw_to_address(&self, pubkey: EcPoint) -> Result<NetworkAddress, NodeError> {
         let mut url = self.url.clone();
         url.path_segments_mut()
@@ -39,3 +48,116 @@ impl<'a> UtilsEndpoint<'a> {
         .address)
     }
 }
+
+/// Result of [`UtilsEndpoint::validate_address`]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressValidity {
+    pub address: String,
+    pub is_valid: bool,
+    /// Why the address is invalid
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddressToRawResponse {
+    raw: EcPoint,
+}
+
+#[derive(Deserialize)]
+struct ErgoTreeToAddressResponse {
+    address: NetworkAddress,
+}
+
+impl<'a> UtilsEndpoint<'a> {
+    /// Generates a random 32 bytes seed (/utils/seed endpoint)
+    pub async fn seed(&self) -> Result<Vec<u8>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("seed");
+        Ok(process_response::<Base16DecodedBytes>(
+            self.client.get(url).send().await.map_err(NodeError::Http)?,
+        )
+        .await?
+        .0)
+    }
+
+    /// Generates a random seed of `length` bytes (/utils/seed/{length} endpoint)
+    pub async fn seed_with_length(&self, length: u32) -> Result<Vec<u8>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["seed", &length.to_string()]);
+        Ok(process_response::<Base16DecodedBytes>(
+            self.client.get(url).send().await.map_err(NodeError::Http)?,
+        )
+        .await?
+        .0)
+    }
+
+    /// Hashes the UTF-8 bytes of `input` with Blake2b256 (/utils/hash/blake2b endpoint).
+    /// [`local::blake2b_hash`] gives the same result without a request
+    pub async fn blake2b_hash(&self, input: &str) -> Result<Digest32, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["hash", "blake2b"]);
+        process_response(
+            self.client
+                .post(url)
+                .json(input)
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await
+    }
+
+    /// Checks whether `address` is a valid address on the node's network
+    /// (/utils/address/{address} endpoint). See [`local::validate_address`]
+    pub async fn validate_address(&self, address: &str) -> Result<AddressValidity, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["address", address]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
+    /// Get the public key of a P2PK address (/utils/addressToRaw/{address} endpoint).
+    /// See [`local::address_to_raw`]
+    pub async fn address_to_raw(&self, address: &NetworkAddress) -> Result<EcPoint, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["addressToRaw", &address.to_base58()]);
+        Ok(process_response::<AddressToRawResponse>(
+            self.client.get(url).send().await.map_err(NodeError::Http)?,
+        )
+        .await?
+        .raw)
+    }
+
+    /// Get the address of boxes guarded by `ergo_tree` on the node's network
+    /// (/utils/ergoTreeToAddress endpoint). See [`local::ergo_tree_to_address`]
+    pub async fn ergo_tree_to_address(
+        &self,
+        ergo_tree: &ErgoTree,
+    ) -> Result<NetworkAddress, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("ergoTreeToAddress");
+        let ergo_tree = Base16EncodedBytes::new(&ergo_tree.sigma_serialize_bytes()?);
+        Ok(process_response::<ErgoTreeToAddressResponse>(
+            self.client
+                .post(url)
+                .json(&ergo_tree)
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await?
+        .address)
+    }
+}
@@ -0,0 +1,56 @@
+//! Local equivalents of [`super::UtilsEndpoint`] calls that give the same result as the node
+//! without a request. Generating seeds is left to the node
+use ergo_lib::{
+    ergo_chain_types::{blake2b256_hash, Digest32, EcPoint},
+    ergotree_ir::{
+        chain::address::{Address, AddressEncoder, AddressError, NetworkAddress, NetworkPrefix},
+        ergo_tree::ErgoTree,
+        sigma_protocol::sigma_boolean::ProveDlog,
+    },
+};
+
+use super::AddressValidity;
+use crate::node::NodeError;
+
+/// Hashes the UTF-8 bytes of `input` with Blake2b256
+pub fn blake2b_hash(input: &str) -> Digest32 {
+    blake2b256_hash(input.as_bytes())
+}
+
+/// Checks whether `address` is a valid address on `network`
+pub fn validate_address(address: &str, network: NetworkPrefix) -> AddressValidity {
+    let error = AddressEncoder::new(network)
+        .parse_address_from_str(address)
+        .err()
+        .map(|e| e.to_string());
+    AddressValidity {
+        address: address.to_string(),
+        is_valid: error.is_none(),
+        error,
+    }
+}
+
+/// Get the P2PK address of `pubkey` on `network`
+pub fn raw_to_address(pubkey: EcPoint, network: NetworkPrefix) -> NetworkAddress {
+    NetworkAddress::new(network, &Address::P2Pk(ProveDlog::new(pubkey)))
+}
+
+/// Get the public key of a P2PK address, or `None` for other address types
+pub fn address_to_raw(address: &NetworkAddress) -> Option<EcPoint> {
+    match address.address() {
+        Address::P2Pk(prove_dlog) => Some(*prove_dlog.h),
+        _ => None,
+    }
+}
+
+/// Get the address of boxes guarded by `ergo_tree` on `network`
+pub fn ergo_tree_to_address(
+    ergo_tree: &ErgoTree,
+    network: NetworkPrefix,
+) -> Result<NetworkAddress, NodeError> {
+    let address = Address::recreate_from_ergo_tree(ergo_tree).map_err(|e| match e {
+        AddressError::ErgoTreeError(e) => NodeError::ErgoTree(e),
+        AddressError::UnexpectedErgoTree(_, reason) => NodeError::UnexpectedErgoTree(reason),
+    })?;
+    Ok(NetworkAddress::new(network, &address))
+}
@@ -0,0 +1,73 @@
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox};
+use reqwest::{Client, StatusCode};
+use url::Url;
+
+use crate::node::{process_response, NodeError};
+
+#[derive(Debug, Clone)]
+pub struct UtxoEndpoint<'a> {
+    client: &'a Client,
+    url: Url,
+}
+
+impl<'a> UtxoEndpoint<'a> {
+    pub fn new(client: &'a Client, mut url: Url) -> Result<Self, NodeError> {
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("utxo");
+        Ok(Self { client, url })
+    }
+
+    /// Get an unspent box from the UTXO set (/utxo/byId/{boxId} endpoint)
+    pub async fn by_id(&self, box_id: &BoxId) -> Result<ErgoBox, NodeError> {
+        let mut url = self.url.clone();
+        let box_id = box_id.to_string();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["byId", &box_id]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
+    /// Like [`Self::by_id`], but returns `None` if the box isn't in the UTXO set
+    pub async fn find_by_id(&self, box_id: &BoxId) -> Result<Option<ErgoBox>, NodeError> {
+        let mut url = self.url.clone();
+        let box_id = box_id.to_string();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["byId", &box_id]);
+        let response = self.client.get(url).send().await.map_err(NodeError::Http)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        process_response(response).await.map(Some)
+    }
+
+    /// Get an unspent box from the UTXO set or the outputs of mempool transactions
+    /// (/utxo/withPool/byId/{boxId} endpoint)
+    pub async fn with_pool_by_id(&self, box_id: &BoxId) -> Result<ErgoBox, NodeError> {
+        let mut url = self.url.clone();
+        let box_id = box_id.to_string();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["withPool", "byId", &box_id]);
+        process_response(self.client.get(url).send().await.map_err(NodeError::Http)?).await
+    }
+
+    /// Get unspent boxes from the UTXO set or the outputs of mempool transactions
+    /// (/utxo/withPool/byIds endpoint). Boxes that weren't found are left out of the result
+    pub async fn with_pool_by_ids(&self, box_ids: &[BoxId]) -> Result<Vec<ErgoBox>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .extend(&["withPool", "byIds"]);
+        process_response(
+            self.client
+                .post(url)
+                .json(box_ids)
+                .send()
+                .await
+                .map_err(NodeError::Http)?,
+        )
+        .await
+    }
+}
@@ -4,10 +4,11 @@ pub mod transaction;
 use self::{boxes::BoxesEndpoint, transaction::TransactionEndpoint};
 use crate::node::{process_response, NodeError};
 use ergo_lib::{
+    chain::transaction::TxId,
     ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
-    ergotree_ir::chain::address::NetworkAddress,
+    ergotree_ir::chain::{address::NetworkAddress, ergo_box::ErgoBox},
 };
-use reqwest::{Client, Url};
+use reqwest::{Client, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -25,12 +26,12 @@ impl<'a> WalletEndpoint<'a> {
         Ok(Self { client, url })
     }
 
-    pub fn transaction(&self) -> Result<TransactionEndpoint, NodeError> {
-        TransactionEndpoint::new(&self.client, self.url.clone())
+    pub fn transaction(&self) -> Result<TransactionEndpoint<'_>, NodeError> {
+        TransactionEndpoint::new(self.client, self.url.clone())
     }
 
-    pub fn boxes(&self) -> Result<BoxesEndpoint, NodeError> {
-        BoxesEndpoint::new(&self.client, self.url.clone())
+    pub fn boxes(&self) -> Result<BoxesEndpoint<'_>, NodeError> {
+        BoxesEndpoint::new(self.client, self.url.clone())
     }
 }
 
@@ -54,6 +55,16 @@ impl<'a> WalletEndpoint<'a> {
     }
 }
 
+/// Mined transaction tracked by the wallet
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletTransaction {
+    pub id: TxId,
+    pub inclusion_height: u32,
+    pub num_confirmations: u32,
+    pub outputs: Vec<ErgoBox>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnlockRequest {
@@ -86,6 +97,29 @@ impl<'a> WalletEndpoint<'a> {
         .map(|_| ())
     }
 
+    /// Looks up a mined transaction the wallet tracks (/wallet/transactionById endpoint).
+    /// Returns `None` if the wallet doesn't know the transaction
+    pub async fn find_transaction_by_id(
+        &self,
+        tx_id: &TxId,
+    ) -> Result<Option<WalletTransaction>, NodeError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| NodeError::BaseUrl)?
+            .push("transactionById");
+        let response = self
+            .client
+            .get(url)
+            .query(&[("id", tx_id.to_string())])
+            .send()
+            .await
+            .map_err(NodeError::Http)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        process_response(response).await.map(Some)
+    }
+
     pub async fn unlock(&self, password: String) -> Result<(), NodeError> {
         let mut url = self.url.clone();
         url.path_segments_mut()
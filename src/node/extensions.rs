@@ -1,28 +1,140 @@
+pub mod chain_follower;
+pub mod confirmation;
+pub mod contract;
+pub mod contract_cache;
+pub mod eip4;
+pub mod fee;
+pub mod mempool_watcher;
+mod polling;
+pub mod reservation;
+pub mod scan_feed;
+pub mod verification;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use self::{
+    chain_follower::{ChainFollower, Checkpoint},
+    confirmation::{ConfirmationResult, ConfirmationTracker, TxOutcome},
+    contract::{CompiledContract, SourceWithConstants},
+    contract_cache::ContractCache,
+    eip4::{TokenIssuance, TokenMetadata},
+    fee::FeeEstimator,
+    mempool_watcher::MempoolWatcher,
+    reservation::BoxReservations,
+    scan_feed::ScanFeed,
+    verification::TransactionVerification,
+};
 use super::{
     endpoints::{
-        scan::{ScanBox, ScanQuery},
-        NodeEndpoint,
+        scan::{RegisteredScan, Scan, ScanBox, ScanQuery},
+        script::{ScriptContext, ScriptExecution},
+        NodeEndpoint, NodePaginationQuery,
     },
     NodeError,
 };
 use ergo_lib::{
-    chain::transaction::{unsigned::UnsignedTransaction, Transaction},
-    ergo_chain_types::EcPoint,
+    chain::{
+        ergo_box::box_builder::ErgoBoxCandidateBuilder,
+        ergo_state_context::{ErgoStateContext, Headers},
+        transaction::{
+            ergo_transaction::ErgoTransaction, unsigned::UnsignedTransaction, Transaction, TxId,
+        },
+    },
+    ergo_chain_types::{BlockId, EcPoint, Header, PreHeader, Votes},
     ergotree_interpreter::sigma_protocol::private_input::DlogProverInput,
     ergotree_ir::{
-        chain::{address::NetworkAddress, ergo_box::ErgoBox},
+        chain::{
+            address::{Address, NetworkAddress},
+            ergo_box::{box_value::BoxValue, BoxId, ErgoBox, ErgoBoxCandidate},
+            token::{Token, TokenAmount, TokenId},
+        },
         ergo_tree::ErgoTree,
+        mir::constant::Constant,
+    },
+    wallet::{
+        box_selector::{
+            sum_tokens, sum_tokens_from_boxes, sum_value, BoxSelection, BoxSelector,
+            BoxSelectorError, SimpleBoxSelector,
+        },
+        tx_builder::{TxBuilder, SUGGESTED_TX_FEE},
+        tx_context::TransactionContext,
+        Wallet,
     },
 };
+use serde::Serialize;
+
+/// An output of a payment made with [`NodeExtension::pay`]
+#[derive(Debug, Clone)]
+pub struct PaymentRecipient {
+    pub address: Address,
+    pub nano_ergs: u64,
+    pub tokens: Vec<Token>,
+}
+
+/// How often [`NodeExtension::wait_for_confirmation`] checks on the transaction
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Maximum number of times a transaction is rebuilt to cover the fee suggested by a
+/// [`FeeEstimator`], which can grow with every input added to pay it
+const MAX_FEE_ESTIMATION_ROUNDS: u32 = 3;
+
+/// Number of previous headers in an [`ErgoStateContext`]
+pub const NUM_STATE_CONTEXT_HEADERS: usize = 10;
+
+/// Holds the last state context built by [`NodeExtension::state_context`], shared between clones
+#[derive(Debug, Clone, Default)]
+pub struct StateContextCache(Arc<Mutex<Option<(BlockId, ErgoStateContext)>>>);
 
 #[derive(Debug)]
 pub struct NodeExtension<'a> {
     endpoints: &'a NodeEndpoint,
+    state_context_cache: Option<&'a StateContextCache>,
+    box_reservations: Option<&'a BoxReservations>,
+    fee_estimator: Option<FeeEstimator<'a>>,
+    contract_cache: Option<&'a ContractCache>,
 }
 
 impl<'a> NodeExtension<'a> {
     pub fn new(endpoints: &'a NodeEndpoint) -> Self {
-        Self { endpoints }
+        Self {
+            endpoints,
+            state_context_cache: None,
+            box_reservations: None,
+            fee_estimator: None,
+            contract_cache: None,
+        }
+    }
+
+    /// Reuses state contexts from `cache` as long as the node's best header doesn't change
+    pub fn with_state_context_cache(mut self, cache: &'a StateContextCache) -> Self {
+        self.state_context_cache = Some(cache);
+        self
+    }
+
+    /// Skips boxes reserved in `reservations` when selecting boxes, and reserves the boxes picked
+    /// by [`Self::reserve_boxes`] and the transaction builders. Reservations are released if
+    /// submitting the transaction spending them fails and marked as spent if it succeeds
+    pub fn with_box_reservations(mut self, reservations: &'a BoxReservations) -> Self {
+        self.box_reservations = Some(reservations);
+        self
+    }
+
+    /// Uses `fee_estimator` to pick the fee of transactions built without an explicit fee,
+    /// instead of [`SUGGESTED_TX_FEE`]
+    pub fn with_fee_estimator(mut self, fee_estimator: FeeEstimator<'a>) -> Self {
+        self.fee_estimator = Some(fee_estimator);
+        self
+    }
+
+    /// Looks compiled contracts up in `cache` before asking the node to compile them,
+    /// and stores contracts the node compiled in it
+    pub fn with_contract_cache(mut self, cache: &'a ContractCache) -> Self {
+        self.contract_cache = Some(cache);
+        self
     }
 
     pub async fn get_utxos(&self) -> Result<Vec<ErgoBox>, NodeError> {
@@ -37,35 +149,287 @@ impl<'a> NodeExtension<'a> {
             .collect::<Vec<_>>())
     }
 
-    fn take_until_amount(
+    /// Selects boxes out of `boxes` covering `nano_erg_amount` and `tokens` using ergo-lib's
+    /// [`SimpleBoxSelector`]. Any change box is guaranteed to hold at least
+    /// [`BoxValue::SAFE_USER_MIN`] nanoergs.
+    ///
+    /// If `boxes` can't cover the target, [`NodeError::InsufficientFunds`] reports both the
+    /// missing nanoergs and the missing tokens.
+    /// With [`Self::with_box_reservations`], reserved boxes are left out. The selected boxes
+    /// aren't reserved, see [`Self::reserve_boxes`] for that
+    pub fn select_boxes(
         &self,
+        boxes: Vec<ErgoBox>,
         nano_erg_amount: u64,
+        tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, NodeError> {
+        let boxes = match self.box_reservations {
+            Some(reservations) => reservations.lock().available(boxes),
+            None => boxes,
+        };
+        select_available_boxes(boxes, nano_erg_amount, tokens)
+    }
+
+    /// Selects boxes like [`Self::select_boxes`] and reserves them in the reservations set with
+    /// [`Self::with_box_reservations`] in the same step, so concurrent callers never get the same
+    /// boxes. The caller must either submit a transaction spending them through this extension or
+    /// pass them to [`Self::release_reservations`]. Without reservations this is the same as
+    /// [`Self::select_boxes`]
+    pub fn reserve_boxes(
+        &self,
         boxes: Vec<ErgoBox>,
-    ) -> Result<Vec<ErgoBox>, NodeError> {
-        let mut running_total = 0;
-        let utxos = boxes
-            .into_iter()
-            .take_while(|b| {
-                let keep_taking = running_total < nano_erg_amount;
-                running_total += b.value.as_u64();
-                keep_taking
-            })
-            .collect::<Vec<_>>();
-        if running_total >= nano_erg_amount {
-            Ok(utxos)
-        } else {
-            Err(NodeError::InsufficientFunds {
-                requested: nano_erg_amount,
-                found: running_total,
-            })?
+        nano_erg_amount: u64,
+        tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, NodeError> {
+        let Some(reservations) = self.box_reservations else {
+            return select_available_boxes(boxes, nano_erg_amount, tokens);
+        };
+        let mut reservations = reservations.lock();
+        let selection =
+            select_available_boxes(reservations.available(boxes), nano_erg_amount, tokens)?;
+        reservations.reserve(selection.boxes.iter().map(|b| b.box_id()));
+        Ok(selection)
+    }
+
+    /// Selects and reserves wallet boxes covering `nano_erg_amount` and `tokens`, see
+    /// [`Self::reserve_boxes`]
+    pub async fn reserve_utxos(
+        &self,
+        nano_erg_amount: u64,
+        tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, NodeError> {
+        self.reserve_boxes(self.get_utxos().await?, nano_erg_amount, tokens)
+    }
+
+    /// Makes boxes reserved by [`Self::reserve_boxes`] available for selection again, e.g. when
+    /// the transaction spending them is abandoned
+    pub fn release_reservations(&self, box_ids: &[BoxId]) {
+        if let Some(reservations) = self.box_reservations {
+            reservations.release(box_ids);
         }
     }
 
+    /// Selects wallet boxes covering `nano_erg_amount` and `tokens`, see [`Self::select_boxes`]
+    pub async fn select_utxos(
+        &self,
+        nano_erg_amount: u64,
+        tokens: &[Token],
+    ) -> Result<BoxSelection<ErgoBox>, NodeError> {
+        self.select_boxes(self.get_utxos().await?, nano_erg_amount, tokens)
+    }
+
     pub async fn get_utxos_summing_amount(
         &self,
         nano_erg_amount: u64,
     ) -> Result<Vec<ErgoBox>, NodeError> {
-        self.take_until_amount(nano_erg_amount, self.get_utxos().await?)
+        Ok(self
+            .select_utxos(nano_erg_amount, &[])
+            .await?
+            .boxes
+            .to_vec())
+    }
+
+    /// Builds a transaction paying `recipients` out of the wallet's boxes.
+    /// Change is sent to the wallet's change address and `fee` to the miners. Without a `fee`,
+    /// it's picked by the fee estimator set with [`Self::with_fee_estimator`], or is
+    /// [`SUGGESTED_TX_FEE`] if there is none.
+    /// With [`Self::with_box_reservations`], the inputs are reserved like in
+    /// [`Self::reserve_boxes`]. Submitting the transaction through this extension settles them,
+    /// otherwise they must be passed to [`Self::release_reservations`]
+    pub async fn build_payment(
+        &self,
+        recipients: &[PaymentRecipient],
+        fee: Option<BoxValue>,
+    ) -> Result<UnsignedTransaction, NodeError> {
+        let current_height = self.current_height().await?;
+        let change_address = self.change_address().await?;
+        let mut outputs = Vec::with_capacity(recipients.len());
+        let mut tokens = vec![];
+        let mut nano_erg_amount = 0;
+        for recipient in recipients {
+            let mut builder = ErgoBoxCandidateBuilder::new(
+                recipient.nano_ergs.try_into()?,
+                recipient.address.script()?,
+                current_height,
+            );
+            for token in &recipient.tokens {
+                builder.add_token(token.clone());
+            }
+            outputs.push(builder.build()?);
+            nano_erg_amount += recipient.nano_ergs;
+            tokens.extend_from_slice(&recipient.tokens);
+        }
+        self.build_transaction(
+            current_height,
+            change_address,
+            nano_erg_amount,
+            &tokens,
+            fee,
+            |_| Ok(outputs.clone()),
+        )
+        .await
+    }
+
+    /// Pays `recipients` out of the wallet's boxes, see [`Self::build_payment`] for the fee.
+    /// Returns the signed transaction that was submitted.
+    pub async fn pay(&self, recipients: &[PaymentRecipient]) -> Result<Transaction, NodeError> {
+        let unsigned_tx = self.build_payment(recipients, None).await?;
+        self.sign_and_submit(unsigned_tx).await
+    }
+
+    /// Mints a new token following EIP-4 and sends it to the wallet's change address.
+    /// The fee is picked the same way as in [`Self::pay`].
+    /// Returns the id of the new token and the signed transaction that was submitted.
+    /// Reserved inputs are released if signing or submitting fails, see
+    /// [`Self::with_box_reservations`]
+    pub async fn mint_token(
+        &self,
+        issuance: &TokenIssuance,
+    ) -> Result<(TokenId, Transaction), NodeError> {
+        let current_height = self.current_height().await?;
+        let change_address = self.change_address().await?;
+        let box_value = BoxValue::SAFE_USER_MIN;
+        let unsigned_tx = self
+            .build_transaction(
+                current_height,
+                change_address.clone(),
+                *box_value.as_u64(),
+                &[],
+                None,
+                |box_selection| {
+                    // EIP-4: the id of a new token is the id of the first input
+                    let token_id = TokenId::from(box_selection.boxes.first().box_id());
+                    Ok(vec![issuance.box_candidate(
+                        token_id,
+                        box_value,
+                        change_address.script()?,
+                        current_height,
+                    )?])
+                },
+            )
+            .await?;
+        let token_id = TokenId::from(unsigned_tx.inputs.first().box_id);
+        Ok((token_id, self.sign_and_submit(unsigned_tx).await?))
+    }
+
+    /// Builds a transaction funded by the wallet's boxes with the outputs returned by `outputs`
+    /// for the selected inputs. Change is sent to `change_address`, see [`Self::build_payment`]
+    /// for how a missing `fee` is picked.
+    /// With a fee estimator, the transaction is rebuilt with more inputs while the estimate for
+    /// the built transaction is higher than the fee it pays
+    async fn build_transaction(
+        &self,
+        current_height: u32,
+        change_address: Address,
+        nano_erg_amount: u64,
+        tokens: &[Token],
+        fee: Option<BoxValue>,
+        outputs: impl Fn(&BoxSelection<ErgoBox>) -> Result<Vec<ErgoBoxCandidate>, NodeError>,
+    ) -> Result<UnsignedTransaction, NodeError> {
+        let (mut fee, fee_estimator) = match (fee, &self.fee_estimator) {
+            (Some(fee), _) => (fee, None),
+            (None, Some(fee_estimator)) => (fee_estimator.min_fee(), Some(fee_estimator)),
+            (None, None) => (SUGGESTED_TX_FEE(), None),
+        };
+        let mut round = 0;
+        loop {
+            round += 1;
+            let box_selection = self
+                .reserve_utxos(nano_erg_amount + fee.as_u64(), tokens)
+                .await?;
+            let input_ids = box_selection
+                .boxes
+                .iter()
+                .map(|b| b.box_id())
+                .collect::<Vec<_>>();
+            let result = async {
+                let outputs = outputs(&box_selection)?;
+                let unsigned_tx = TxBuilder::new(
+                    box_selection,
+                    outputs,
+                    current_height,
+                    fee,
+                    change_address.clone(),
+                )
+                .build()?;
+                let estimate = match fee_estimator {
+                    Some(fee_estimator) if round < MAX_FEE_ESTIMATION_ROUNDS => {
+                        Some(fee_estimator.estimate_for(&unsigned_tx).await?)
+                    }
+                    _ => None,
+                };
+                Ok::<_, NodeError>((unsigned_tx, estimate))
+            }
+            .await;
+            match result {
+                Ok((_, Some(estimate))) if estimate > fee => {
+                    self.release_reservations(&input_ids);
+                    fee = estimate;
+                }
+                Ok((unsigned_tx, _)) => return Ok(unsigned_tx),
+                Err(e) => {
+                    self.release_reservations(&input_ids);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Fetches a token's issuance box through the indexer and decodes its EIP-4 metadata
+    pub async fn token_metadata(&self, token_id: &TokenId) -> Result<TokenMetadata, NodeError> {
+        let blockchain = self.endpoints.blockchain()?;
+        let token = blockchain.get_token_by_id(token_id).await?;
+        let issuance_box = blockchain.get_box_by_id(&token.box_id).await?;
+        Ok(TokenMetadata::from_issuance_box(&issuance_box.ergo_box)?)
+    }
+
+    /// Builds the state context needed to sign or verify transactions locally from the node's
+    /// last headers and current parameters. The pre-header describes the block following the
+    /// best header: its parent is the best header, its timestamp is the current time (but after
+    /// the best header's) and, since the next miner isn't known, it reuses the best header's
+    /// miner public key and votes for nothing
+    pub async fn state_context(&self) -> Result<ErgoStateContext, NodeError> {
+        let info = self.endpoints.root()?.info().await?;
+        if let (Some(cache), Some(best_header_id)) =
+            (self.state_context_cache, &info.best_header_id)
+        {
+            if let Some((cached_id, state_context)) = &*cache.0.lock().unwrap() {
+                if cached_id == best_header_id {
+                    let mut state_context = state_context.clone();
+                    state_context.pre_header = upcoming_pre_header(&state_context.headers[0]);
+                    return Ok(state_context);
+                }
+            }
+        }
+
+        let mut headers = self
+            .endpoints
+            .blocks()?
+            .last_headers(NUM_STATE_CONTEXT_HEADERS as u32)
+            .await?;
+        // the state context expects the newest header first
+        headers.reverse();
+        let headers: Headers = headers
+            .try_into()
+            .map_err(|headers: Vec<Header>| NodeError::NotEnoughHeaders(headers.len()))?;
+        let best_header_id = headers[0].id;
+        let state_context =
+            ErgoStateContext::new(upcoming_pre_header(&headers[0]), headers, info.parameters);
+        if let Some(cache) = self.state_context_cache {
+            *cache.0.lock().unwrap() = Some((best_header_id, state_context.clone()));
+        }
+        Ok(state_context)
+    }
+
+    async fn current_height(&self) -> Result<u32, NodeError> {
+        Ok(self.endpoints.root()?.info().await?.full_height as u32)
+    }
+
+    async fn change_address(&self) -> Result<Address, NodeError> {
+        Ok(
+            NetworkAddress::try_from(self.endpoints.wallet()?.status().await?.change_address)?
+                .address(),
+        )
     }
 
     /// Signs and submits the supplied transaction.
@@ -74,25 +438,245 @@ impl<'a> NodeExtension<'a> {
         &self,
         unsigned_tx: UnsignedTransaction,
     ) -> Result<Transaction, NodeError> {
-        let signed_tx = self
-            .endpoints
-            .wallet()?
-            .transaction()?
-            .sign(unsigned_tx, None, None)
+        let input_ids = unsigned_tx.inputs_ids().collect::<Vec<_>>();
+        let result = async {
+            let signed_tx = self
+                .endpoints
+                .wallet()?
+                .transaction()?
+                .sign(unsigned_tx, None, None)
+                .await?;
+            self.endpoints.transactions()?.submit(&signed_tx).await?;
+            Ok(signed_tx)
+        }
+        .await;
+        self.settle_reservations(&input_ids, &result);
+        result
+    }
+
+    /// Marks `input_ids` as spent if their transaction was submitted and releases them otherwise
+    fn settle_reservations<T>(&self, input_ids: &[BoxId], result: &Result<T, NodeError>) {
+        match (self.box_reservations, result) {
+            (Some(reservations), Ok(_)) => reservations.mark_spent(input_ids),
+            _ => self.release_reservations(input_ids),
+        }
+    }
+
+    /// Signs the supplied transaction locally with the secrets in `wallet`, so the keys never
+    /// reach the node. Input and data input boxes are fetched from the node's UTXO set and mempool,
+    /// and the state context is built with [`Self::state_context`]
+    pub async fn sign_locally(
+        &self,
+        unsigned_tx: UnsignedTransaction,
+        wallet: &Wallet,
+    ) -> Result<Transaction, NodeError> {
+        let tx_context = self.transaction_context(unsigned_tx).await?;
+        let state_context = self.state_context().await?;
+        Ok(wallet.sign_transaction(tx_context, &state_context, None)?)
+    }
+
+    /// Signs the supplied transaction locally with [`Self::sign_locally`] and submits it.
+    /// Returns the signed transaction that was submitted.
+    pub async fn sign_locally_and_submit(
+        &self,
+        unsigned_tx: UnsignedTransaction,
+        wallet: &Wallet,
+    ) -> Result<Transaction, NodeError> {
+        let input_ids = unsigned_tx.inputs_ids().collect::<Vec<_>>();
+        let result = async {
+            let signed_tx = self.sign_locally(unsigned_tx, wallet).await?;
+            self.endpoints.transactions()?.submit(&signed_tx).await?;
+            Ok(signed_tx)
+        }
+        .await;
+        self.settle_reservations(&input_ids, &result);
+        result
+    }
+
+    /// Verifies `tx` locally the same way the node would before accepting it into the mempool,
+    /// with input and data input boxes fetched from the node's UTXO set and mempool
+    pub async fn verify_transaction(
+        &self,
+        tx: &Transaction,
+    ) -> Result<TransactionVerification, NodeError> {
+        let tx_context = self.transaction_context(tx.clone()).await?;
+        let state_context = self.state_context().await?;
+        Ok(TransactionVerification::new(&tx_context, &state_context)?)
+    }
+
+    /// Submits `tx` only if it passes [`Self::verify_transaction`].
+    /// Returns the id of the submitted transaction
+    pub async fn verify_and_submit(&self, tx: &Transaction) -> Result<String, NodeError> {
+        let result = async {
+            self.verify_transaction(tx).await?.validation?;
+            self.endpoints.transactions()?.submit(tx).await
+        }
+        .await;
+        self.settle_reservations(&tx.inputs_ids().collect::<Vec<_>>(), &result);
+        result
+    }
+
+    /// Fetches the input and data input boxes of `tx` from the node's UTXO set and mempool
+    pub async fn transaction_context<T: ErgoTransaction>(
+        &self,
+        tx: T,
+    ) -> Result<TransactionContext<T>, NodeError> {
+        let inputs = self.get_boxes(&tx.inputs_ids().collect::<Vec<_>>()).await?;
+        let data_inputs = self
+            .get_boxes(
+                &tx.data_inputs()
+                    .into_iter()
+                    .flatten()
+                    .map(|data_input| data_input.box_id)
+                    .collect::<Vec<_>>(),
+            )
             .await?;
-        self.endpoints.transactions()?.submit(&signed_tx).await?;
-        Ok(signed_tx)
+        Ok(TransactionContext::new(tx, inputs, data_inputs)?)
+    }
+
+    /// Gets boxes from the node's UTXO set and mempool, in the same order as `box_ids`
+    pub async fn get_boxes(&self, box_ids: &[BoxId]) -> Result<Vec<ErgoBox>, NodeError> {
+        if box_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut boxes = self
+            .endpoints
+            .utxo()?
+            .with_pool_by_ids(box_ids)
+            .await?
+            .into_iter()
+            .map(|b| (b.box_id(), b))
+            .collect::<HashMap<_, _>>();
+        box_ids
+            .iter()
+            .map(|box_id| boxes.remove(box_id).ok_or(NodeError::BoxNotFound(*box_id)))
+            .collect()
+    }
+
+    /// Waits until the transaction `tx_id` has `depth` confirmations, it is evicted from the mempool,
+    /// or `timeout` passes. Inclusion is found through the indexer or the wallet, see
+    /// [`ConfirmationTracker`]
+    pub async fn wait_for_confirmation(
+        &self,
+        tx_id: TxId,
+        depth: u32,
+        timeout: Duration,
+    ) -> Result<ConfirmationResult, NodeError> {
+        let mut tracker = ConfirmationTracker::new(self.endpoints, tx_id, depth);
+        let mut events = vec![];
+        let wait = async {
+            loop {
+                events.extend(tracker.poll().await?);
+                if let Some(outcome) = tracker.outcome() {
+                    return Ok::<_, NodeError>(outcome.clone());
+                }
+                tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+            }
+        };
+        let outcome = match tokio::time::timeout(timeout, wait).await {
+            Ok(outcome) => outcome?,
+            Err(_) => TxOutcome::TimedOut,
+        };
+        Ok(ConfirmationResult { outcome, events })
     }
 
-    /// Compiles the provided Ergo Script source code into a ErgoTree instance
+    /// Compiles the provided Ergo Script source code into a ErgoTree instance.
+    /// Scripts the node compiles to a P2PK address, like `PK("...")`, are supported as well
     pub async fn compile_contract(&self, source: &str) -> Result<ErgoTree, NodeError> {
-        let addr = self.endpoints.script()?.p2s_address(source).await?;
-        Ok(NetworkAddress::try_from(addr)
-            .unwrap()
-            .address()
-            .script()
-            .unwrap())
+        self.compile(source, &BTreeMap::new(), None).await
+    }
+
+    /// Compiles `source` into an ErgoTree of version `tree_version`, with every name in
+    /// `constants` bound to its value. Positions of compile errors refer to `source`.
+    /// Names must be ErgoScript identifiers, and every value must remain a distinct constant of
+    /// the tree, see [`CompiledContract::new`]
+    pub async fn compile_contract_with_constants(
+        &self,
+        source: &str,
+        constants: &BTreeMap<String, Constant>,
+        tree_version: u8,
+    ) -> Result<CompiledContract, NodeError> {
+        let ergo_tree = self.compile(source, constants, Some(tree_version)).await?;
+        Ok(CompiledContract::new(ergo_tree, constants)?)
     }
+
+    async fn compile(
+        &self,
+        source: &str,
+        constants: &BTreeMap<String, Constant>,
+        tree_version: Option<u8>,
+    ) -> Result<ErgoTree, NodeError> {
+        let cache_key = match self.contract_cache {
+            Some(cache) => {
+                // the node can be upgraded while the cache is in use, so its version is
+                // refreshed every NODE_VERSION_REFRESH_INTERVAL
+                let node_url = self.endpoints.url();
+                let node_version = match cache.node_version(node_url) {
+                    Some(node_version) => node_version,
+                    None => {
+                        let node_version = self.endpoints.root()?.info().await?.app_version;
+                        cache.use_node_version(node_url, &node_version);
+                        node_version
+                    }
+                };
+                let key = ContractCache::key(&node_version, source, constants, tree_version)?;
+                if let Some(ergo_tree) = cache.get(&key) {
+                    return Ok(ergo_tree);
+                }
+                Some((cache, key))
+            }
+            None => None,
+        };
+
+        let with_constants = SourceWithConstants::new(source, constants)?;
+        let script = self.endpoints.script()?;
+        let addr = match tree_version {
+            Some(tree_version) => {
+                script
+                    .p2s_address_with_tree_version(&with_constants.source, tree_version)
+                    .await
+            }
+            None => script.p2s_address(&with_constants.source).await,
+        }
+        .map_err(|e| match e {
+            NodeError::Compile(error) => NodeError::Compile(with_constants.map_error(error)),
+            e => e,
+        })?;
+        let ergo_tree = addr.address().script()?;
+        if let Some((cache, key)) = cache_key {
+            cache.insert(key, &ergo_tree)?;
+        }
+        Ok(ergo_tree)
+    }
+
+    /// Reduces `source` on the node as the script guarding input `self_index` of `tx`.
+    /// Input and data input boxes are fetched from the node's UTXO set and mempool,
+    /// and the chain state is taken from [`Self::state_context`]
+    pub async fn execute_script<T: ErgoTransaction + Serialize>(
+        &self,
+        source: &str,
+        tx: T,
+        self_index: usize,
+    ) -> Result<ScriptExecution, NodeError> {
+        let boxes_to_spend = self.get_boxes(&tx.inputs_ids().collect::<Vec<_>>()).await?;
+        let data_boxes = self
+            .get_boxes(
+                &tx.data_inputs()
+                    .into_iter()
+                    .flatten()
+                    .map(|data_input| data_input.box_id)
+                    .collect::<Vec<_>>(),
+            )
+            .await?;
+        let state_context = self.state_context().await?;
+        let context =
+            ScriptContext::new(tx, boxes_to_spend, data_boxes, &state_context, self_index)?;
+        self.endpoints
+            .script()?
+            .execute_with_context(source, &context)
+            .await
+    }
+
     /// Get private key for EcPoint if it is in wallet database
     pub async fn get_private_key(&self, public_key: EcPoint) -> Result<DlogProverInput, NodeError> {
         let address = self.endpoints.utils()?.raw_to_address(public_key).await?;
@@ -125,4 +709,391 @@ impl<'a> NodeExtension<'a> {
         }
         Ok(boxes)
     }
+
+    /// Returns a feed of box changes for `scan_id`, see [`ScanFeed::into_stream`]
+    pub fn scan_feed(&self, scan_id: u32, include_unconfirmed: bool) -> ScanFeed<'a> {
+        ScanFeed::new(self.endpoints, scan_id, include_unconfirmed)
+    }
+
+    /// Returns a follower of the node's best chain, starting after `checkpoint` or after the
+    /// current best block, see [`ChainFollower::into_stream`]
+    pub fn chain_follower(&self, checkpoint: Option<Checkpoint>) -> ChainFollower<'a> {
+        ChainFollower::new(self.endpoints, checkpoint)
+    }
+
+    /// Returns a fee estimator with the default target wait and minimum fee, to be customized
+    /// and passed to [`Self::with_fee_estimator`]
+    pub fn fee_estimator(&self) -> FeeEstimator<'a> {
+        FeeEstimator::new(self.endpoints)
+    }
+
+    /// Returns a watcher of the node's mempool, see [`MempoolWatcher::into_stream`]
+    pub fn mempool_watcher(&self) -> MempoolWatcher<'a> {
+        MempoolWatcher::new(self.endpoints)
+    }
+
+    /// Get all transactions in the mempool. Keeps calling /transactions/unconfirmed until every
+    /// page is retrieved
+    pub async fn get_all_unconfirmed(&self) -> Result<Vec<Transaction>, NodeError> {
+        let transactions_endpoint = self.endpoints.transactions()?;
+        let mut query = NodePaginationQuery {
+            limit: 100,
+            offset: 0,
+        };
+        let mut transactions = vec![];
+        loop {
+            let page = transactions_endpoint.unconfirmed(&query).await?;
+            if page.is_empty() {
+                break;
+            }
+            query.offset += page.len() as u32;
+            transactions.extend(page);
+        }
+        Ok(transactions)
+    }
+
+    /// Get the wallet's boxes as they will be once the mempool is confirmed: boxes spent by
+    /// mempool transactions are left out and mempool outputs sent to a wallet address are added.
+    /// Transactions built from these boxes can chain off the wallet's own pending transactions
+    pub async fn get_utxos_with_mempool(&self) -> Result<Vec<ErgoBox>, NodeError> {
+        let wallet_trees = self
+            .endpoints
+            .wallet()?
+            .get_addresses()
+            .await?
+            .into_iter()
+            .map(|address| address.address().script())
+            .collect::<Result<Vec<_>, _>>()?;
+        let boxes = self.get_utxos().await?;
+        self.apply_mempool(boxes, |b| wallet_trees.contains(&b.ergo_tree))
+            .await
+    }
+
+    /// Get the boxes of scan `scan_id` as they will be once the mempool is confirmed, see
+    /// [`Self::get_utxos_with_mempool`]. Mempool outputs are added if they match the scan's
+    /// tracking rule
+    pub async fn get_all_unspent_boxes_with_mempool(
+        &self,
+        scan_id: u32,
+    ) -> Result<Vec<ErgoBox>, NodeError> {
+        let tracking_rule = self
+            .endpoints
+            .scan()?
+            .list_all()
+            .await?
+            .into_iter()
+            .find(|registered_scan| registered_scan.scan_id == scan_id)
+            .ok_or(NodeError::ScanNotFound(scan_id))?
+            .scan
+            .tracking_rule;
+        let boxes = self
+            .get_all_unspent_boxes(scan_id, false)
+            .await?
+            .into_iter()
+            .map(|scan_box| scan_box.ergo_box)
+            .collect();
+        self.apply_mempool(boxes, |b| tracking_rule.matches(b))
+            .await
+    }
+
+    async fn apply_mempool(
+        &self,
+        boxes: Vec<ErgoBox>,
+        is_tracked: impl Fn(&ErgoBox) -> bool,
+    ) -> Result<Vec<ErgoBox>, NodeError> {
+        let mempool = self.get_all_unconfirmed().await?;
+        let spent = mempool
+            .iter()
+            .flat_map(|tx| tx.inputs_ids())
+            .collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        Ok(boxes
+            .into_iter()
+            .chain(
+                mempool
+                    .into_iter()
+                    .flat_map(|tx| tx.outputs.to_vec())
+                    .filter(|output| is_tracked(output)),
+            )
+            .filter(|b| !spent.contains(&b.box_id()) && seen.insert(b.box_id()))
+            .collect())
+    }
+
+    /// Makes the node's registered scans match `scans`, using `scan_name` as the key.
+    ///
+    /// Scans that are missing on the node are registered. A scan registered under the same name
+    /// but with a different tracking rule, wallet interaction or `remove_offchain` flag is
+    /// deregistered and registered again, and duplicate registrations of the same name are removed.
+    /// Scans with names not in `scans` are only deregistered if `deregister_stale` is set.
+    ///
+    /// Returns the scan id for every scan name in `scans`. Fails without changing anything if
+    /// a name appears more than once in `scans`
+    pub async fn sync_scans(
+        &self,
+        scans: &[Scan<'_>],
+        deregister_stale: bool,
+    ) -> Result<HashMap<String, u32>, NodeError> {
+        let mut names = HashSet::new();
+        if let Some(scan) = scans
+            .iter()
+            .find(|scan| !names.insert(scan.scan_name.as_ref()))
+        {
+            return Err(NodeError::DuplicateScanName(scan.scan_name.to_string()));
+        }
+
+        let scan_endpoint = self.endpoints.scan()?;
+        let mut registered: HashMap<String, Vec<RegisteredScan>> = HashMap::new();
+        for registered_scan in scan_endpoint.list_all().await? {
+            registered
+                .entry(registered_scan.scan.scan_name.to_string())
+                .or_default()
+                .push(registered_scan);
+        }
+
+        let mut scan_ids = HashMap::new();
+        for scan in scans {
+            let existing = registered
+                .remove(scan.scan_name.as_ref())
+                .unwrap_or_default();
+            let (mut up_to_date, outdated): (Vec<_>, Vec<_>) = existing
+                .into_iter()
+                .partition(|registered_scan| same_scan(&registered_scan.scan, scan));
+            let scan_id = if up_to_date.is_empty() {
+                scan_endpoint.register(scan).await?
+            } else {
+                up_to_date.remove(0).scan_id
+            };
+            for registered_scan in up_to_date.into_iter().chain(outdated) {
+                scan_endpoint.deregister(registered_scan.scan_id).await?;
+            }
+            scan_ids.insert(scan.scan_name.to_string(), scan_id);
+        }
+
+        if deregister_stale {
+            for registered_scan in registered.into_values().flatten() {
+                scan_endpoint.deregister(registered_scan.scan_id).await?;
+            }
+        }
+        Ok(scan_ids)
+    }
+}
+
+/// Selects boxes out of `boxes`, which are all available for selection, see
+/// [`NodeExtension::select_boxes`]
+fn select_available_boxes(
+    boxes: Vec<ErgoBox>,
+    nano_erg_amount: u64,
+    tokens: &[Token],
+) -> Result<BoxSelection<ErgoBox>, NodeError> {
+    let missing_nano_ergs = nano_erg_amount.saturating_sub(sum_value(&boxes));
+    let available_tokens = sum_tokens_from_boxes(&boxes).map_err(BoxSelectorError::from)?;
+    let missing_tokens = sum_tokens(Some(tokens))
+        .map_err(BoxSelectorError::from)?
+        .into_iter()
+        .filter_map(|(token_id, amount)| {
+            let available = available_tokens.get(&token_id).map_or(0, |a| *a.as_u64());
+            // a missing amount of 0 isn't a valid TokenAmount, filtering out covered tokens
+            TokenAmount::try_from(amount.as_u64().saturating_sub(available))
+                .ok()
+                .map(|amount| Token { token_id, amount })
+        })
+        .collect::<Vec<_>>();
+    if missing_nano_ergs > 0 || !missing_tokens.is_empty() {
+        return Err(NodeError::InsufficientFunds {
+            missing_nano_ergs,
+            missing_tokens,
+        });
+    }
+
+    // the selector's target must be a valid box value, so smaller targets (including 0 for
+    // token-only selections) select at least `BoxValue::MIN` and hand the excess back as change
+    let target = BoxValue::try_from(nano_erg_amount).unwrap_or(BoxValue::MIN);
+    let mut selection = SimpleBoxSelector::new().select(boxes, target, tokens)?;
+    let excess = target.as_u64() - nano_erg_amount;
+    if excess > 0 {
+        match selection.change_boxes.first_mut() {
+            Some(change) => change.value = (change.value.as_u64() + excess).try_into()?,
+            // the excess alone is below `BoxValue::MIN`, too little for a change box
+            None => {
+                return Err(NodeError::InsufficientFunds {
+                    missing_nano_ergs: BoxValue::SAFE_USER_MIN.as_u64() - excess,
+                    missing_tokens: vec![],
+                })
+            }
+        }
+    }
+    if let Some(change) = selection
+        .change_boxes
+        .iter()
+        .find(|change| change.value < BoxValue::SAFE_USER_MIN)
+    {
+        return Err(NodeError::InsufficientFunds {
+            missing_nano_ergs: BoxValue::SAFE_USER_MIN.as_u64() - change.value.as_u64(),
+            missing_tokens: vec![],
+        });
+    }
+    Ok(selection)
+}
+
+/// Pre-header of the block following `best_header`, which is the earliest block a transaction
+/// signed now can be included in. The timestamp is the current time, but after `best_header`'s.
+/// The miner of the next block isn't known, so the best header's miner stands in for it
+fn upcoming_pre_header(best_header: &Header) -> PreHeader {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64);
+    PreHeader {
+        version: best_header.version,
+        parent_id: best_header.id,
+        timestamp: now.max(best_header.timestamp + 1),
+        n_bits: best_header.n_bits,
+        height: best_header.height + 1,
+        miner_pk: best_header.autolykos_solution.miner_pk.clone(),
+        votes: Votes([0; 3]),
+    }
+}
+
+/// Compares scans by their JSON encoding, since the same constant can be held in different
+/// in-memory representations depending on whether it was built locally or parsed from the node
+fn same_scan(registered: &Scan<'_>, desired: &Scan<'_>) -> bool {
+    registered.wallet_interaction == desired.wallet_interaction
+        && registered.remove_offchain == desired.remove_offchain
+        && serde_json::to_value(&registered.tracking_rule).ok()
+            == serde_json::to_value(&desired.tracking_rule).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ergo_lib::{
+        chain::transaction::TxId,
+        ergo_chain_types::{ec_point::generator, Digest32},
+        ergotree_ir::{
+            chain::ergo_box::{BoxId, NonMandatoryRegisters},
+            sigma_protocol::sigma_boolean::ProveDlog,
+        },
+    };
+
+    use super::*;
+
+    const ERG: u64 = 1_000_000_000;
+
+    fn token(byte: u8, amount: u64) -> Token {
+        Token {
+            token_id: BoxId::from(Digest32::from([byte; 32])).into(),
+            amount: amount.try_into().unwrap(),
+        }
+    }
+
+    fn test_box(index: u16, nano_ergs: u64, tokens: Vec<Token>) -> ErgoBox {
+        ErgoBox::new(
+            nano_ergs.try_into().unwrap(),
+            Address::P2Pk(ProveDlog::new(generator())).script().unwrap(),
+            (!tokens.is_empty()).then(|| tokens.try_into().unwrap()),
+            NonMandatoryRegisters::empty(),
+            0,
+            TxId::zero(),
+            index,
+        )
+        .unwrap()
+    }
+
+    fn change_value(selection: &BoxSelection<ErgoBox>) -> u64 {
+        selection
+            .change_boxes
+            .iter()
+            .map(|change| *change.value.as_u64())
+            .sum()
+    }
+
+    #[test]
+    fn target_below_min_box_value_returns_excess_as_change() {
+        let selection = select_available_boxes(vec![test_box(0, ERG, vec![])], 1000, &[]).unwrap();
+        assert_eq!(selection.boxes.len(), 1);
+        assert_eq!(change_value(&selection), ERG - 1000);
+    }
+
+    #[test]
+    fn token_only_selection() {
+        let boxes = vec![
+            test_box(0, 2 * ERG, vec![]),
+            test_box(1, ERG, vec![token(1, 10)]),
+        ];
+        let selection = select_available_boxes(boxes.clone(), 0, &[token(1, 4)]).unwrap();
+        assert_eq!(selection.boxes.as_slice(), &boxes[1..]);
+        assert_eq!(selection.change_boxes.len(), 1);
+        assert_eq!(change_value(&selection), ERG);
+        assert_eq!(
+            selection.change_boxes[0]
+                .tokens
+                .as_ref()
+                .unwrap()
+                .as_slice(),
+            &[token(1, 6)]
+        );
+    }
+
+    #[test]
+    fn change_below_safe_minimum_is_insufficient() {
+        let boxes = vec![test_box(0, ERG, vec![])];
+        match select_available_boxes(boxes.clone(), ERG - 100_000, &[]) {
+            Err(NodeError::InsufficientFunds {
+                missing_nano_ergs,
+                missing_tokens,
+            }) => {
+                assert_eq!(
+                    missing_nano_ergs,
+                    BoxValue::SAFE_USER_MIN.as_u64() - 100_000
+                );
+                assert!(missing_tokens.is_empty());
+            }
+            other => panic!("{other:?}"),
+        }
+        // change below `BoxValue::MIN` is already rejected by the selector
+        assert!(matches!(
+            select_available_boxes(boxes, ERG - 1000, &[]),
+            Err(NodeError::BoxSelection(
+                BoxSelectorError::NotEnoughCoinsForChangeBox(_)
+            ))
+        ));
+
+        // a selection without change can't take the excess of a target below the minimum
+        let boxes = vec![test_box(0, *BoxValue::MIN.as_u64(), vec![])];
+        match select_available_boxes(boxes, 800, &[]) {
+            Err(NodeError::InsufficientFunds {
+                missing_nano_ergs, ..
+            }) => assert_eq!(
+                missing_nano_ergs,
+                BoxValue::SAFE_USER_MIN.as_u64() - (BoxValue::MIN.as_u64() - 800)
+            ),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_missing_ergs_and_tokens() {
+        let boxes = vec![test_box(0, ERG, vec![token(1, 1)])];
+        match select_available_boxes(boxes, 3 * ERG, &[token(1, 3), token(2, 1)]) {
+            Err(NodeError::InsufficientFunds {
+                missing_nano_ergs,
+                mut missing_tokens,
+            }) => {
+                assert_eq!(missing_nano_ergs, 2 * ERG);
+                missing_tokens.sort_by_key(|token| Vec::<u8>::from(token.token_id));
+                assert_eq!(missing_tokens, vec![token(1, 2), token(2, 1)]);
+            }
+            other => panic!("{other:?}"),
+        }
+
+        let boxes = vec![test_box(0, 3 * ERG, vec![token(1, 1)])];
+        match select_available_boxes(boxes, ERG, &[token(1, 2)]) {
+            Err(NodeError::InsufficientFunds {
+                missing_nano_ergs,
+                missing_tokens,
+            }) => {
+                assert_eq!(missing_nano_ergs, 0);
+                assert_eq!(missing_tokens, vec![token(1, 1)]);
+            }
+            other => panic!("{other:?}"),
+        }
+    }
 }
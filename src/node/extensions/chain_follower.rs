@@ -0,0 +1,189 @@
+use std::{collections::VecDeque, time::Duration};
+
+use ergo_lib::{
+    chain::transaction::Transaction,
+    ergo_chain_types::{BlockId, Header},
+};
+use futures_util::Stream;
+
+use super::polling::{poll_stream, Poller};
+use crate::node::{endpoints::NodeEndpoint, NodeError};
+
+/// Number of recent headers kept by a [`ChainFollower`] unless configured otherwise
+pub const DEFAULT_WINDOW_SIZE: usize = 100;
+
+/// Maximum number of blocks applied in a single [`ChainFollower::poll`]
+const MAX_BLOCKS_PER_POLL: u32 = 50;
+
+/// Last block processed by a [`ChainFollower`]. Persisting it allows resuming after a restart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub height: u32,
+    pub block_id: BlockId,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    /// A block was added to the best chain
+    BlockApplied(Header, Vec<Transaction>),
+    /// A previously applied block is no longer part of the best chain.
+    /// Blocks are rolled back newest first, before the blocks of the new best chain are applied
+    BlockRolledBack(Header),
+}
+
+/// Follows the node's best chain of full blocks, reporting applied and rolled back blocks in order
+#[derive(Debug)]
+pub struct ChainFollower<'a> {
+    endpoints: &'a NodeEndpoint,
+    /// Block to continue from when `window` is empty
+    start: Option<Checkpoint>,
+    /// Recently applied headers, oldest first
+    window: VecDeque<Header>,
+    window_size: usize,
+    caught_up: bool,
+}
+
+impl<'a> ChainFollower<'a> {
+    /// Creates a follower that reports blocks after `checkpoint`,
+    /// or after the current best block if no checkpoint is given
+    pub fn new(endpoints: &'a NodeEndpoint, checkpoint: Option<Checkpoint>) -> Self {
+        Self {
+            endpoints,
+            start: checkpoint,
+            window: VecDeque::new(),
+            window_size: DEFAULT_WINDOW_SIZE,
+            caught_up: false,
+        }
+    }
+
+    pub fn with_window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size.max(1);
+        self
+    }
+
+    /// The last block reported as applied, or the starting checkpoint if none was applied yet
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        self.window
+            .back()
+            .map(|header| Checkpoint {
+                height: header.height,
+                block_id: header.id,
+            })
+            .or(self.start)
+    }
+
+    /// Whether the last poll reached the node's best full block
+    pub fn is_caught_up(&self) -> bool {
+        self.caught_up
+    }
+
+    /// Checks the node for new blocks and forks once and returns the resulting events.
+    /// If a request fails after some events were produced, those events are returned and the
+    /// failed step is retried on the next poll
+    pub async fn poll(&mut self) -> Result<Vec<ChainEvent>, NodeError> {
+        let mut events = vec![];
+        match self.advance(&mut events).await {
+            Err(e) if events.is_empty() => Err(e),
+            _ => Ok(events),
+        }
+    }
+
+    async fn advance(&mut self, events: &mut Vec<ChainEvent>) -> Result<(), NodeError> {
+        let blocks = self.endpoints.blocks()?;
+
+        // roll back blocks that are no longer part of the best chain
+        loop {
+            let tip = self.tip().await?;
+            if blocks.block_at_height(tip.height).await? == Some(tip.id) {
+                break;
+            }
+            self.window.pop_back();
+            if self.window.is_empty() {
+                self.start = Some(Checkpoint {
+                    height: tip.height - 1,
+                    block_id: tip.parent_id,
+                });
+            }
+            events.push(ChainEvent::BlockRolledBack(tip));
+        }
+
+        let full_height = self.endpoints.root()?.info().await?.full_height as u32;
+        let tip_height = self.tip().await?.height;
+        let to_height = full_height.min(tip_height + MAX_BLOCKS_PER_POLL);
+        self.caught_up = tip_height >= full_height;
+        if self.caught_up {
+            return Ok(());
+        }
+        let mut headers = blocks.chain_slice(tip_height..to_height).await?;
+        headers.retain(|header| header.height > tip_height && header.height <= to_height);
+        headers.sort_by_key(|header| header.height);
+        for header in headers {
+            let tip = self.tip().await?;
+            // the best chain changed since the fork check, the next poll will roll back
+            if header.parent_id != tip.id {
+                break;
+            }
+            let transactions = blocks.transactions(&header.id).await?;
+            events.push(ChainEvent::BlockApplied(header.clone(), transactions));
+            self.caught_up = header.height >= full_height;
+            self.window.push_back(header);
+            if self.window.len() > self.window_size {
+                self.window.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the last applied header, loading the starting block into the window if it's empty
+    async fn tip(&mut self) -> Result<Header, NodeError> {
+        if let Some(tip) = self.window.back() {
+            return Ok(tip.clone());
+        }
+        let blocks = self.endpoints.blocks()?;
+        let header = match self.start {
+            Some(checkpoint) => {
+                let header = blocks.header(&checkpoint.block_id).await?;
+                if header.height != checkpoint.height {
+                    return Err(NodeError::InvalidCheckpoint {
+                        height: checkpoint.height,
+                        block_id: checkpoint.block_id,
+                    });
+                }
+                header
+            }
+            None => {
+                let full_height = self.endpoints.root()?.info().await?.full_height as u32;
+                let block_id = blocks
+                    .block_at_height(full_height)
+                    .await?
+                    .ok_or(NodeError::NoBlocks)?;
+                blocks.header(&block_id).await?
+            }
+        };
+        self.window.push_back(header.clone());
+        Ok(header)
+    }
+
+    /// Turns the follower into a stream of events. While catching up, blocks are fetched without
+    /// delay, afterwards the node is polled every `poll_interval`.
+    /// Errors are yielded as they occur and polling continues afterwards
+    pub fn into_stream(
+        self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<ChainEvent, NodeError>> + 'a {
+        poll_stream(self, poll_interval)
+    }
+}
+
+impl Poller for ChainFollower<'_> {
+    type Event = ChainEvent;
+
+    async fn poll(&mut self) -> Result<Vec<ChainEvent>, NodeError> {
+        ChainFollower::poll(self).await
+    }
+
+    /// Catching up is done without waiting between polls
+    fn should_wait(&self, events: &[ChainEvent]) -> bool {
+        self.is_caught_up() || events.is_empty()
+    }
+}
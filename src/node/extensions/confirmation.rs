@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use ergo_lib::{
+    chain::transaction::TxId, ergo_chain_types::BlockId, ergotree_ir::chain::ergo_box::BoxId,
+};
+use futures_util::Stream;
+
+use super::polling::{poll_stream, Poller};
+use crate::node::{endpoints::NodeEndpoint, NodeError};
+
+/// Change in the state of a transaction tracked by [`ConfirmationTracker`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxEvent {
+    InMempool,
+    /// The transaction left the mempool without being included in a block
+    DroppedFromMempool,
+    IncludedInBlock {
+        height: u32,
+        block_id: BlockId,
+    },
+    Confirmations(u32),
+    /// The block the transaction was included in is no longer part of the best chain
+    Orphaned {
+        height: u32,
+        block_id: BlockId,
+    },
+}
+
+/// Final state of a transaction tracked by [`ConfirmationTracker`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxOutcome {
+    /// The transaction reached the requested number of confirmations
+    Finalized { height: u32, block_id: BlockId },
+    /// The transaction was dropped from the mempool and isn't part of the best chain
+    Evicted,
+    /// Neither of the above happened before the timeout
+    TimedOut,
+}
+
+/// Returned by [`super::NodeExtension::wait_for_confirmation`]
+#[derive(Debug, Clone)]
+pub struct ConfirmationResult {
+    pub outcome: TxOutcome,
+    /// Every state change seen while waiting, in order
+    pub events: Vec<TxEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxState {
+    Unknown,
+    InMempool,
+    Included {
+        height: u32,
+        block_id: BlockId,
+        confirmations: u32,
+    },
+}
+
+/// Number of polls in a row a transaction that was seen before must be missing from the mempool,
+/// the indexer and the UTXO set before it's considered evicted, unless configured otherwise
+pub const DEFAULT_EVICTION_POLLS: u32 = 3;
+
+/// Follows a transaction through the mempool and the chain until it reaches the requested depth.
+/// Inclusion is looked up through the node's indexer (/blockchain endpoints) and, for nodes
+/// without it, the wallet, which only knows transactions involving its own addresses or scans
+#[derive(Debug)]
+pub struct ConfirmationTracker<'a> {
+    endpoints: &'a NodeEndpoint,
+    tx_id: TxId,
+    depth: u32,
+    eviction_polls: u32,
+    state: TxState,
+    /// Outputs of the transaction, known once it was seen in the mempool
+    output_ids: Vec<BoxId>,
+    /// Number of polls in a row the transaction was missing since it was last seen
+    misses: u32,
+    outcome: Option<TxOutcome>,
+}
+
+impl<'a> ConfirmationTracker<'a> {
+    pub fn new(endpoints: &'a NodeEndpoint, tx_id: TxId, depth: u32) -> Self {
+        Self {
+            endpoints,
+            tx_id,
+            depth,
+            eviction_polls: DEFAULT_EVICTION_POLLS,
+            state: TxState::Unknown,
+            output_ids: vec![],
+            misses: 0,
+            outcome: None,
+        }
+    }
+
+    /// Number of polls in a row the transaction must be missing before it's considered evicted.
+    /// A single missed poll can be a transaction mined between lookups or an indexer lagging
+    /// behind the chain. At least 1
+    pub fn with_eviction_polls(mut self, eviction_polls: u32) -> Self {
+        self.eviction_polls = eviction_polls.max(1);
+        self
+    }
+
+    /// `Finalized` or `Evicted` once the transaction reached one of those states
+    pub fn outcome(&self) -> Option<&TxOutcome> {
+        self.outcome.as_ref()
+    }
+
+    /// Looks the transaction up in the mempool, then the indexer, then the wallet. Returns `None`
+    /// if it's in none of them, but its outputs are in the UTXO set, i.e. it was mined and not
+    /// indexed yet
+    async fn lookup(&mut self) -> Result<Option<TxState>, NodeError> {
+        if let Some(tx) = self
+            .endpoints
+            .transactions()?
+            .unconfirmed_by_id(&self.tx_id)
+            .await?
+        {
+            self.output_ids = tx.outputs.iter().map(|output| output.box_id()).collect();
+            return Ok(Some(TxState::InMempool));
+        }
+        if let Some(tx) = self
+            .endpoints
+            .blockchain()?
+            .find_transaction_by_id(&self.tx_id)
+            .await?
+        {
+            return Ok(Some(TxState::Included {
+                height: tx.inclusion_height,
+                block_id: tx.block_id,
+                confirmations: tx.num_confirmations,
+            }));
+        }
+        // the node doesn't serve /blockchain at all with the indexer disabled, which looks the
+        // same as an unknown transaction
+        if let Some(tx) = self
+            .endpoints
+            .wallet()?
+            .find_transaction_by_id(&self.tx_id)
+            .await?
+        {
+            // the wallet doesn't report the block, so it's taken from the best chain at the
+            // inclusion height. Missing if the chain was reorganized in between
+            let block_id = self
+                .endpoints
+                .blocks()?
+                .block_at_height(tx.inclusion_height)
+                .await?;
+            return Ok(block_id.map(|block_id| TxState::Included {
+                height: tx.inclusion_height,
+                block_id,
+                confirmations: tx.num_confirmations,
+            }));
+        }
+        let utxo = self.endpoints.utxo()?;
+        for output_id in &self.output_ids {
+            if utxo.find_by_id(output_id).await?.is_some() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(TxState::Unknown))
+    }
+
+    /// Looks the transaction up once and returns the state changes since the last poll
+    pub async fn poll(&mut self) -> Result<Vec<TxEvent>, NodeError> {
+        let mut events = vec![];
+        if self.outcome.is_some() {
+            return Ok(events);
+        }
+        let new_state = match self.lookup().await? {
+            // a transaction that was never seen might not have propagated to the node yet
+            Some(TxState::Unknown) if self.state == TxState::Unknown => return Ok(events),
+            Some(TxState::Unknown) => {
+                self.misses += 1;
+                if self.misses < self.eviction_polls {
+                    return Ok(events);
+                }
+                TxState::Unknown
+            }
+            Some(new_state) => {
+                self.misses = 0;
+                new_state
+            }
+            None => {
+                self.misses = 0;
+                return Ok(events);
+            }
+        };
+
+        if let TxState::Included {
+            height, block_id, ..
+        } = self.state
+        {
+            let still_included =
+                matches!(new_state, TxState::Included { block_id: id, .. } if id == block_id);
+            if !still_included {
+                events.push(TxEvent::Orphaned { height, block_id });
+            }
+        }
+        match new_state {
+            TxState::Included {
+                height,
+                block_id,
+                confirmations,
+            } => {
+                match self.state {
+                    TxState::Included {
+                        block_id: previous_id,
+                        confirmations: previous_confirmations,
+                        ..
+                    } if previous_id == block_id => {
+                        if confirmations != previous_confirmations {
+                            events.push(TxEvent::Confirmations(confirmations));
+                        }
+                    }
+                    _ => {
+                        events.push(TxEvent::IncludedInBlock { height, block_id });
+                        events.push(TxEvent::Confirmations(confirmations));
+                    }
+                }
+                if confirmations >= self.depth {
+                    self.outcome = Some(TxOutcome::Finalized { height, block_id });
+                }
+            }
+            TxState::InMempool => {
+                if self.state != TxState::InMempool {
+                    events.push(TxEvent::InMempool);
+                }
+            }
+            TxState::Unknown => {
+                events.push(TxEvent::DroppedFromMempool);
+                self.outcome = Some(TxOutcome::Evicted);
+            }
+        }
+        self.state = new_state;
+        Ok(events)
+    }
+
+    /// Turns the tracker into a stream of state changes, polling the node every `poll_interval`.
+    /// The stream ends once the transaction is finalized or evicted.
+    /// Errors are yielded as they occur and polling continues afterwards
+    pub fn into_stream(
+        self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<TxEvent, NodeError>> + 'a {
+        poll_stream(self, poll_interval)
+    }
+}
+
+impl Poller for ConfirmationTracker<'_> {
+    type Event = TxEvent;
+
+    async fn poll(&mut self) -> Result<Vec<TxEvent>, NodeError> {
+        ConfirmationTracker::poll(self).await
+    }
+
+    fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use ergo_lib::{
+    ergo_chain_types::{blake2b256_hash, Digest32},
+    ergotree_ir::{
+        ergo_tree::{ErgoTree, ErgoTreeError},
+        mir::constant::Constant,
+        serialization::{SigmaSerializable, SigmaSerializationError},
+    },
+};
+
+use crate::node::endpoints::script::CompileError;
+
+/// ErgoScript keywords, which can't name a constant
+const KEYWORDS: &[&str] = &[
+    "_",
+    "abstract",
+    "case",
+    "catch",
+    "class",
+    "def",
+    "do",
+    "else",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "for",
+    "forSome",
+    "fun",
+    "if",
+    "implicit",
+    "import",
+    "lazy",
+    "macro",
+    "match",
+    "new",
+    "null",
+    "object",
+    "override",
+    "package",
+    "private",
+    "protected",
+    "return",
+    "sealed",
+    "super",
+    "this",
+    "throw",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "val",
+    "var",
+    "while",
+    "with",
+    "yield",
+];
+
+/// A contract compiled by [`super::NodeExtension::compile_contract_with_constants`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledContract {
+    /// Constants are segregated, so they can be replaced with [`Self::with_constant`]
+    /// without compiling again
+    pub ergo_tree: ErgoTree,
+    /// Index in the ErgoTree's constants of every named constant the contract was compiled with
+    pub constant_indices: BTreeMap<String, usize>,
+    /// The ErgoTree's root expression without its constants. Contracts compiled from the same
+    /// source with different constants share it
+    pub template_bytes: Vec<u8>,
+    /// Blake2b256 hash of `template_bytes`
+    pub template_hash: Digest32,
+}
+
+impl CompiledContract {
+    /// Locates every value of `constants` among the constants of `ergo_tree`. Fails if a value
+    /// isn't among them, e.g. because the compiler folded it into an expression, or if it can't
+    /// be told apart from another constant with the same value
+    pub fn new(
+        ergo_tree: ErgoTree,
+        constants: &BTreeMap<String, Constant>,
+    ) -> Result<Self, ContractError> {
+        let tree_constants = ergo_tree
+            .get_constants()?
+            .iter()
+            .map(Constant::sigma_serialize_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut constant_indices = BTreeMap::new();
+        for (name, constant) in constants {
+            let bytes = constant.sigma_serialize_bytes()?;
+            let mut matches = tree_constants
+                .iter()
+                .enumerate()
+                .filter(|(_, tree_constant)| **tree_constant == bytes)
+                .map(|(index, _)| index);
+            let index = match (matches.next(), matches.next()) {
+                (Some(index), None) => index,
+                (None, _) => return Err(ContractError::ConstantNotFound(name.clone())),
+                (Some(_), Some(_)) => return Err(ContractError::AmbiguousConstant(name.clone())),
+            };
+            if constant_indices.values().any(|claimed| *claimed == index) {
+                return Err(ContractError::AmbiguousConstant(name.clone()));
+            }
+            constant_indices.insert(name.clone(), index);
+        }
+        let template_bytes = ergo_tree.template_bytes()?;
+        Ok(Self {
+            template_hash: blake2b256_hash(&template_bytes),
+            template_bytes,
+            constant_indices,
+            ergo_tree,
+        })
+    }
+
+    /// The contract with the constant `name` set to `value`, which must be of the same type
+    pub fn with_constant(&self, name: &str, value: Constant) -> Result<Self, ContractError> {
+        let index = *self
+            .constant_indices
+            .get(name)
+            .ok_or_else(|| ContractError::ConstantNotFound(name.to_string()))?;
+        Ok(Self {
+            ergo_tree: self.ergo_tree.clone().with_constant(index, value)?,
+            ..self.clone()
+        })
+    }
+}
+
+/// ErgoScript source defining every constant in `constants` as a `val` before `source`.
+/// Values are passed serialized through `deserialize[T]`, which the compiler evaluates into
+/// constants of the tree. Names must be ErgoScript identifiers, so they can't inject code
+pub(super) struct SourceWithConstants {
+    pub source: String,
+    /// Number of lines added before the first line of the original source
+    pub prelude_lines: u32,
+}
+
+impl SourceWithConstants {
+    pub fn new(
+        source: &str,
+        constants: &BTreeMap<String, Constant>,
+    ) -> Result<Self, ContractError> {
+        if let Some(name) = constants.keys().find(|name| !is_identifier(name)) {
+            return Err(ContractError::InvalidConstantName(name.clone()));
+        }
+        if constants.is_empty() {
+            return Ok(Self {
+                source: source.to_string(),
+                prelude_lines: 0,
+            });
+        }
+        let mut with_constants = String::from("{\n");
+        for (name, constant) in constants {
+            with_constants.push_str(&format!(
+                "val {name} = deserialize[{}](\"{}\")\n",
+                constant.tpe,
+                bs58::encode(constant.sigma_serialize_bytes()?).into_string()
+            ));
+        }
+        with_constants.push_str(source);
+        with_constants.push_str("\n}");
+        Ok(Self {
+            source: with_constants,
+            prelude_lines: constants.len() as u32 + 1,
+        })
+    }
+
+    /// Moves the position of `error` back to the original source. Errors within the added
+    /// definitions lose their position
+    pub fn map_error(&self, mut error: CompileError) -> CompileError {
+        match error.line {
+            Some(line) if line > self.prelude_lines => {
+                error.line = Some(line - self.prelude_lines);
+            }
+            Some(_) => {
+                error.line = None;
+                error.column = None;
+            }
+            None => {}
+        }
+        error
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ContractError {
+    #[error("{0} is not a valid ErgoScript identifier")]
+    InvalidConstantName(String),
+
+    #[error("Constant {0} is not among the ErgoTree's constants")]
+    ConstantNotFound(String),
+
+    #[error("Constant {0} has the same value as another constant of the ErgoTree")]
+    AmbiguousConstant(String),
+
+    #[error("Invalid ErgoTree")]
+    ErgoTree(#[from] ErgoTreeError),
+
+    #[error("Failed to serialize constant")]
+    Serialization(#[from] SigmaSerializationError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_that_are_not_identifiers() {
+        for name in ["", "1a", "a b", "a=1;sigmaProp(true)}", "val", "_", "é"] {
+            let constants = BTreeMap::from([(name.to_string(), Constant::from(1i32))]);
+            assert!(
+                matches!(
+                    SourceWithConstants::new("sigmaProp(true)", &constants),
+                    Err(ContractError::InvalidConstantName(_))
+                ),
+                "{name}"
+            );
+        }
+        let constants = BTreeMap::from([
+            ("_deadline".to_string(), Constant::from(1i32)),
+            ("owner2".to_string(), Constant::from(2i32)),
+        ]);
+        let with_constants = SourceWithConstants::new("sigmaProp(true)", &constants).unwrap();
+        assert_eq!(with_constants.prelude_lines, 3);
+        assert!(with_constants.source.ends_with("sigmaProp(true)\n}"));
+    }
+
+    #[test]
+    fn maps_errors_back_to_the_source() {
+        let constants = BTreeMap::from([("a".to_string(), Constant::from(1i32))]);
+        let with_constants = SourceWithConstants::new("sigmaProp(a > b)", &constants).unwrap();
+        let error = |line| CompileError {
+            line: Some(line),
+            column: Some(15),
+            message: "not found".to_string(),
+        };
+        assert_eq!(with_constants.map_error(error(3)), error(1));
+        let in_prelude = with_constants.map_error(error(2));
+        assert_eq!((in_prelude.line, in_prelude.column), (None, None));
+    }
+}
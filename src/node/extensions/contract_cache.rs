@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ergo_lib::{
+    ergo_chain_types::{blake2b256_hash, Digest32},
+    ergotree_ir::{
+        ergo_tree::ErgoTree,
+        mir::constant::Constant,
+        serialization::{SigmaSerializable, SigmaSerializationError},
+    },
+};
+use reqwest::Url;
+
+/// How long the version recorded for a node with [`ContractCache::use_node_version`] is reused
+/// before the node is asked again, so upgrades are noticed without a request per compile
+pub const NODE_VERSION_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Version of the node that compiled the entries kept in memory
+    node_version: Option<String>,
+    /// Last version seen for every node using the cache and when it was fetched
+    node_versions: HashMap<Url, (String, Instant)>,
+    trees: HashMap<Digest32, ErgoTree>,
+}
+
+/// Compiled contracts keyed by a hash of the compiling node's version and their source,
+/// constants and tree version, checked by [`super::NodeExtension`] before asking the node to
+/// compile. Shared between clones.
+///
+/// The node's version determines its compiler's version, so contracts compiled by another
+/// version are never returned, even when the cache is shared by clients of different nodes
+#[derive(Debug, Clone, Default)]
+pub struct ContractCache {
+    /// Directory the entries are persisted in, `None` for a cache kept in memory only
+    dir: Option<PathBuf>,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl ContractCache {
+    /// A cache kept in memory, lost when the last clone is dropped
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// A cache persisted in `dir`, which is created if it doesn't exist.
+    /// Every contract is stored in its own file named by its key
+    pub fn on_disk(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir: Some(dir),
+            entries: Arc::default(),
+        })
+    }
+
+    /// Hash identifying a contract compiled by a node of version `node_version` from `source`
+    /// with `constants` and `tree_version`. Contracts compiled without an explicit tree version
+    /// get a different key than any version
+    pub fn key(
+        node_version: &str,
+        source: &str,
+        constants: &BTreeMap<String, Constant>,
+        tree_version: Option<u8>,
+    ) -> Result<Digest32, SigmaSerializationError> {
+        let mut bytes = vec![];
+        // lengths keep different splits of the same bytes from hashing the same
+        bytes.extend_from_slice(&(node_version.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(node_version.as_bytes());
+        match tree_version {
+            Some(tree_version) => bytes.extend_from_slice(&[1, tree_version]),
+            None => bytes.push(0),
+        }
+        for (name, constant) in constants {
+            let constant_bytes = constant.sigma_serialize_bytes()?;
+            bytes.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&(constant_bytes.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&constant_bytes);
+        }
+        bytes.extend_from_slice(source.as_bytes());
+        Ok(blake2b256_hash(&bytes))
+    }
+
+    /// Version recorded for the node at `node_url`, unless it was recorded more than
+    /// [`NODE_VERSION_REFRESH_INTERVAL`] ago
+    pub fn node_version(&self, node_url: &Url) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        let (node_version, fetched_at) = entries.node_versions.get(node_url)?;
+        (fetched_at.elapsed() < NODE_VERSION_REFRESH_INTERVAL).then(|| node_version.clone())
+    }
+
+    /// Records `node_version` as the version of the node at `node_url`, just fetched from it.
+    /// Drops the contracts kept in memory if it differs from the version they were compiled by,
+    /// since their keys can't match anymore. Contracts on disk are kept for nodes of their version
+    pub fn use_node_version(&self, node_url: &Url, node_version: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .node_versions
+            .insert(node_url.clone(), (node_version.to_string(), Instant::now()));
+        if entries.node_version.as_deref() != Some(node_version) {
+            entries.trees.clear();
+            entries.node_version = Some(node_version.to_string());
+        }
+    }
+
+    /// Returns the contract stored under `key`. Unreadable files count as missing
+    pub fn get(&self, key: &Digest32) -> Option<ErgoTree> {
+        if let Some(ergo_tree) = self.entries.lock().unwrap().trees.get(key) {
+            return Some(ergo_tree.clone());
+        }
+        // files are read without holding the lock, so other callers aren't blocked on the disk
+        let bytes = fs::read(self.dir.as_ref()?.join(file_name(key))).ok()?;
+        let ergo_tree = ErgoTree::sigma_parse_bytes(&bytes).ok()?;
+        self.entries
+            .lock()
+            .unwrap()
+            .trees
+            .insert(*key, ergo_tree.clone());
+        Some(ergo_tree)
+    }
+
+    pub fn insert(&self, key: Digest32, ergo_tree: &ErgoTree) -> Result<(), ContractCacheError> {
+        if let Some(dir) = &self.dir {
+            // writing to a temporary file first keeps readers from seeing a partial tree
+            let path = dir.join(file_name(&key));
+            let tmp_path = path.with_extension("tmp");
+            fs::write(&tmp_path, ergo_tree.sigma_serialize_bytes()?)?;
+            fs::rename(tmp_path, path)?;
+        }
+        self.entries
+            .lock()
+            .unwrap()
+            .trees
+            .insert(key, ergo_tree.clone());
+        Ok(())
+    }
+
+    /// Removes every contract, in memory and on disk, e.g. to free the space taken by contracts
+    /// of node versions that are no longer used
+    pub fn clear(&self) -> Result<(), ContractCacheError> {
+        self.entries.lock().unwrap().trees.clear();
+        if let Some(dir) = &self.dir {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "tree")
+                {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn file_name(key: &Digest32) -> String {
+    format!("{}.tree", String::from(*key))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ContractCacheError {
+    #[error("Failed to access contract cache directory")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to serialize ErgoTree")]
+    Serialization(#[from] SigmaSerializationError),
+}
+
+#[cfg(test)]
+mod tests {
+    use ergo_lib::{
+        ergo_chain_types::ec_point::generator,
+        ergotree_ir::{chain::address::Address, sigma_protocol::sigma_boolean::ProveDlog},
+    };
+
+    use super::*;
+
+    fn ergo_tree() -> ErgoTree {
+        Address::P2Pk(ProveDlog::new(generator())).script().unwrap()
+    }
+
+    #[test]
+    fn keys_depend_on_node_version() {
+        let key = |node_version| {
+            ContractCache::key(node_version, "sigmaProp(true)", &BTreeMap::new(), None).unwrap()
+        };
+        assert_eq!(key("5.0.0"), key("5.0.0"));
+        assert_ne!(key("5.0.0"), key("5.0.1"));
+    }
+
+    #[test]
+    fn node_version_change_drops_memory_entries() {
+        let cache = ContractCache::in_memory();
+        let node_url = Url::parse("http://127.0.0.1:9053").unwrap();
+        let key = ContractCache::key("5.0.0", "sigmaProp(true)", &BTreeMap::new(), None).unwrap();
+        cache.use_node_version(&node_url, "5.0.0");
+        cache.insert(key, &ergo_tree()).unwrap();
+        cache.use_node_version(&node_url, "5.0.0");
+        assert_eq!(cache.get(&key), Some(ergo_tree()));
+        cache.use_node_version(&node_url, "5.0.1");
+        assert_eq!(cache.get(&key), None);
+    }
+
+    #[test]
+    fn node_versions_are_recorded_per_node() {
+        let cache = ContractCache::in_memory();
+        let node_url = Url::parse("http://127.0.0.1:9053").unwrap();
+        let other_url = Url::parse("http://127.0.0.1:9052").unwrap();
+        assert_eq!(cache.node_version(&node_url), None);
+        cache.use_node_version(&node_url, "5.0.0");
+        assert_eq!(cache.node_version(&node_url), Some("5.0.0".to_string()));
+        assert_eq!(cache.node_version(&other_url), None);
+    }
+
+    #[test]
+    fn persists_entries_on_disk() {
+        let dir = std::env::temp_dir().join(format!("contract_cache_{}", std::process::id()));
+        let key = ContractCache::key("5.0.0", "sigmaProp(true)", &BTreeMap::new(), None).unwrap();
+        ContractCache::on_disk(&dir)
+            .unwrap()
+            .insert(key, &ergo_tree())
+            .unwrap();
+        let cache = ContractCache::on_disk(&dir).unwrap();
+        assert_eq!(cache.get(&key), Some(ergo_tree()));
+        cache.clear().unwrap();
+        assert_eq!(ContractCache::on_disk(&dir).unwrap().get(&key), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,329 @@
+//! Token metadata as defined in [EIP-4](https://github.com/ergoplatform/eips/blob/master/eip-0004.md)
+use ergo_lib::{
+    chain::ergo_box::box_builder::ErgoBoxCandidateBuilder,
+    ergotree_ir::{
+        chain::{
+            ergo_box::{box_value::BoxValue, ErgoBox, ErgoBoxCandidate, NonMandatoryRegisterId},
+            token::{Token, TokenId},
+        },
+        ergo_tree::ErgoTree,
+        mir::constant::TryExtractInto,
+    },
+};
+
+use crate::node::NodeError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Eip4Error {
+    #[error("Register {0:?} is empty")]
+    MissingRegister(NonMandatoryRegisterId),
+
+    #[error("Register {register:?} doesn't hold a valid value: {reason}")]
+    InvalidRegister {
+        register: NonMandatoryRegisterId,
+        reason: String,
+    },
+}
+
+/// Asset type stored in R7 of a token's issuance box
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetType {
+    NftPicture,
+    NftAudio,
+    NftVideo,
+    NftArtworkCollection,
+    NftFileAttachment,
+    MembershipThresholdSignature,
+    /// Any asset type not known to this crate
+    Other(Vec<u8>),
+}
+
+impl AssetType {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            AssetType::NftPicture => vec![0x01, 0x01],
+            AssetType::NftAudio => vec![0x01, 0x02],
+            AssetType::NftVideo => vec![0x01, 0x03],
+            AssetType::NftArtworkCollection => vec![0x01, 0x04],
+            AssetType::NftFileAttachment => vec![0x01, 0x0f],
+            AssetType::MembershipThresholdSignature => vec![0x02, 0x01],
+            AssetType::Other(bytes) => bytes.clone(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        match bytes {
+            [0x01, 0x01] => AssetType::NftPicture,
+            [0x01, 0x02] => AssetType::NftAudio,
+            [0x01, 0x03] => AssetType::NftVideo,
+            [0x01, 0x04] => AssetType::NftArtworkCollection,
+            [0x01, 0x0f] => AssetType::NftFileAttachment,
+            [0x02, 0x01] => AssetType::MembershipThresholdSignature,
+            _ => AssetType::Other(bytes.to_vec()),
+        }
+    }
+}
+
+/// Optional R7-R9 fields of an issuance box, used by NFTs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetInfo {
+    /// R7
+    pub asset_type: AssetType,
+    /// R8, SHA256 hash of the asset's content
+    pub content_hash: Vec<u8>,
+    /// R9, link to the asset's content
+    pub link: Option<String>,
+}
+
+/// Token to be minted with [`super::NodeExtension::mint_token`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenIssuance {
+    pub name: String,
+    pub description: String,
+    pub decimals: usize,
+    pub amount: u64,
+    pub asset_info: Option<AssetInfo>,
+}
+
+impl TokenIssuance {
+    /// Output of the issuance transaction holding the whole supply of the token `token_id`, with
+    /// the metadata in R4-R9
+    pub fn box_candidate(
+        &self,
+        token_id: TokenId,
+        value: BoxValue,
+        ergo_tree: ErgoTree,
+        creation_height: u32,
+    ) -> Result<ErgoBoxCandidate, NodeError> {
+        let mut builder = ErgoBoxCandidateBuilder::new(value, ergo_tree, creation_height);
+        builder.mint_token(
+            Token {
+                token_id,
+                amount: self.amount.try_into()?,
+            },
+            self.name.clone(),
+            self.description.clone(),
+            self.decimals,
+        );
+        if let Some(asset_info) = &self.asset_info {
+            builder.set_register_value(
+                NonMandatoryRegisterId::R7,
+                asset_info.asset_type.to_bytes().into(),
+            );
+            builder.set_register_value(
+                NonMandatoryRegisterId::R8,
+                asset_info.content_hash.clone().into(),
+            );
+            if let Some(link) = &asset_info.link {
+                builder.set_register_value(
+                    NonMandatoryRegisterId::R9,
+                    link.as_bytes().to_vec().into(),
+                );
+            }
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Metadata decoded from a token's issuance box
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    /// R4
+    pub name: String,
+    /// R5
+    pub description: String,
+    /// R6
+    pub decimals: usize,
+    /// R7-R9, only present if R7 is set
+    pub asset_info: Option<AssetInfo>,
+}
+
+impl TokenMetadata {
+    pub fn from_issuance_box(issuance_box: &ErgoBox) -> Result<Self, Eip4Error> {
+        let name = utf8_register(issuance_box, NonMandatoryRegisterId::R4)?;
+        let description = utf8_register(issuance_box, NonMandatoryRegisterId::R5)?;
+        let decimals = utf8_register(issuance_box, NonMandatoryRegisterId::R6)?;
+        let asset_info = match byte_register(issuance_box, NonMandatoryRegisterId::R7) {
+            Err(Eip4Error::MissingRegister(_)) => None,
+            asset_type => Some(AssetInfo {
+                asset_type: AssetType::from_bytes(&asset_type?),
+                content_hash: byte_register(issuance_box, NonMandatoryRegisterId::R8)?,
+                link: match utf8_register(issuance_box, NonMandatoryRegisterId::R9) {
+                    Err(Eip4Error::MissingRegister(_)) => None,
+                    link => Some(link?),
+                },
+            }),
+        };
+        Ok(Self {
+            name,
+            description,
+            decimals: decimals.parse().map_err(|e| Eip4Error::InvalidRegister {
+                register: NonMandatoryRegisterId::R6,
+                reason: format!("{decimals:?} is not a number of decimals: {e}"),
+            })?,
+            asset_info,
+        })
+    }
+}
+
+fn byte_register(
+    ergo_box: &ErgoBox,
+    register: NonMandatoryRegisterId,
+) -> Result<Vec<u8>, Eip4Error> {
+    let invalid = |reason: String| Eip4Error::InvalidRegister { register, reason };
+    ergo_box
+        .get_register(register.into())
+        .map_err(|e| invalid(e.to_string()))?
+        .ok_or(Eip4Error::MissingRegister(register))?
+        .try_extract_into::<Vec<u8>>()
+        .map_err(|e| invalid(e.to_string()))
+}
+
+fn utf8_register(
+    ergo_box: &ErgoBox,
+    register: NonMandatoryRegisterId,
+) -> Result<String, Eip4Error> {
+    String::from_utf8(byte_register(ergo_box, register)?).map_err(|e| Eip4Error::InvalidRegister {
+        register,
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ergo_lib::{
+        chain::transaction::TxId,
+        ergo_chain_types::{ec_point::generator, Digest32},
+        ergotree_ir::{
+            chain::{
+                address::Address,
+                ergo_box::{BoxId, NonMandatoryRegisters},
+            },
+            mir::constant::Constant,
+            sigma_protocol::sigma_boolean::ProveDlog,
+        },
+    };
+
+    use super::*;
+
+    fn ergo_tree() -> ErgoTree {
+        Address::P2Pk(ProveDlog::new(generator())).script().unwrap()
+    }
+
+    fn issuance_box(issuance: &TokenIssuance) -> ErgoBox {
+        let token_id = BoxId::from(Digest32::from([1; 32])).into();
+        let candidate = issuance
+            .box_candidate(token_id, BoxValue::SAFE_USER_MIN, ergo_tree(), 0)
+            .unwrap();
+        ErgoBox::from_box_candidate(&candidate, TxId::zero(), 0).unwrap()
+    }
+
+    /// Box with R4, R5, ... set to `registers` in order
+    fn box_with_registers(registers: Vec<Vec<u8>>) -> ErgoBox {
+        let registers = registers
+            .into_iter()
+            .map(Constant::from)
+            .collect::<Vec<_>>();
+        ErgoBox::new(
+            BoxValue::SAFE_USER_MIN,
+            ergo_tree(),
+            None,
+            NonMandatoryRegisters::try_from(registers).unwrap(),
+            0,
+            TxId::zero(),
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn minted_metadata_round_trips() {
+        let mut issuance = TokenIssuance {
+            name: "Test token".to_string(),
+            description: "Tökens for tests".to_string(),
+            decimals: 2,
+            amount: 1000,
+            asset_info: None,
+        };
+        let metadata = |issuance: &TokenIssuance| TokenMetadata {
+            name: issuance.name.clone(),
+            description: issuance.description.clone(),
+            decimals: issuance.decimals,
+            asset_info: issuance.asset_info.clone(),
+        };
+        assert_eq!(
+            TokenMetadata::from_issuance_box(&issuance_box(&issuance)).unwrap(),
+            metadata(&issuance)
+        );
+
+        issuance.decimals = 0;
+        issuance.amount = 1;
+        issuance.asset_info = Some(AssetInfo {
+            asset_type: AssetType::NftPicture,
+            content_hash: vec![7; 32],
+            link: None,
+        });
+        assert_eq!(
+            TokenMetadata::from_issuance_box(&issuance_box(&issuance)).unwrap(),
+            metadata(&issuance)
+        );
+
+        issuance.asset_info.as_mut().unwrap().link = Some("ipfs://picture".to_string());
+        assert_eq!(
+            TokenMetadata::from_issuance_box(&issuance_box(&issuance)).unwrap(),
+            metadata(&issuance)
+        );
+    }
+
+    #[test]
+    fn rejects_non_standard_tokens() {
+        let name = b"name".to_vec();
+        let description = b"description".to_vec();
+        assert!(matches!(
+            TokenMetadata::from_issuance_box(&box_with_registers(vec![name.clone()])),
+            Err(Eip4Error::MissingRegister(NonMandatoryRegisterId::R5))
+        ));
+        assert!(matches!(
+            TokenMetadata::from_issuance_box(&box_with_registers(vec![
+                vec![0xff, 0xfe],
+                description.clone(),
+                b"2".to_vec(),
+            ])),
+            Err(Eip4Error::InvalidRegister {
+                register: NonMandatoryRegisterId::R4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            TokenMetadata::from_issuance_box(&box_with_registers(vec![
+                name,
+                description,
+                b"two".to_vec(),
+            ])),
+            Err(Eip4Error::InvalidRegister {
+                register: NonMandatoryRegisterId::R6,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn asset_types_round_trip() {
+        for asset_type in [
+            AssetType::NftPicture,
+            AssetType::NftAudio,
+            AssetType::NftVideo,
+            AssetType::NftArtworkCollection,
+            AssetType::NftFileAttachment,
+            AssetType::MembershipThresholdSignature,
+            AssetType::Other(vec![0x01, 0x05]),
+            AssetType::Other(vec![]),
+        ] {
+            assert_eq!(AssetType::from_bytes(&asset_type.to_bytes()), asset_type);
+        }
+        assert_eq!(
+            AssetType::from_bytes(&[0x01, 0x01, 0x00]),
+            AssetType::Other(vec![0x01, 0x01, 0x00])
+        );
+    }
+}
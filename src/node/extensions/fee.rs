@@ -0,0 +1,158 @@
+use std::{cmp::Ordering, time::Duration};
+
+use ergo_lib::{
+    chain::transaction::{unsigned::UnsignedTransaction, Transaction},
+    ergotree_ir::{
+        chain::ergo_box::box_value::BoxValue, ergo_tree::ErgoTree, serialization::SigmaSerializable,
+    },
+    wallet::{miner_fee::MINERS_FEE_ADDRESS, tx_builder::SUGGESTED_TX_FEE},
+};
+
+use super::NodeExtension;
+use crate::node::{endpoints::NodeEndpoint, NodeError};
+
+/// Confirmation time a [`FeeEstimator`] aims for unless configured otherwise
+pub const DEFAULT_TARGET_WAIT: Duration = Duration::from_secs(10 * 60);
+
+/// Number of bins requested from /transactions/poolHistogram. The first half covers the target
+/// wait time, the second half everything beyond it
+const HISTOGRAM_BINS: u32 = 10;
+
+/// Size of the Schnorr signature proving a P2PK input, which an unsigned transaction lacks
+const P2PK_PROOF_SIZE: usize = 56;
+
+/// Suggests transaction fees from the node's fee recommendation and the mempool's congestion
+#[derive(Debug, Clone)]
+pub struct FeeEstimator<'a> {
+    endpoints: &'a NodeEndpoint,
+    target_wait: Duration,
+    min_fee: BoxValue,
+}
+
+impl<'a> FeeEstimator<'a> {
+    /// Creates an estimator targeting [`DEFAULT_TARGET_WAIT`] with [`SUGGESTED_TX_FEE`] as the
+    /// minimum fee
+    pub fn new(endpoints: &'a NodeEndpoint) -> Self {
+        Self {
+            endpoints,
+            target_wait: DEFAULT_TARGET_WAIT,
+            min_fee: SUGGESTED_TX_FEE(),
+        }
+    }
+
+    /// The node estimates wait times in minutes, shorter targets are rounded up to one minute
+    pub fn with_target_wait(mut self, target_wait: Duration) -> Self {
+        self.target_wait = target_wait.max(Duration::from_secs(60));
+        self
+    }
+
+    pub fn with_min_fee(mut self, min_fee: BoxValue) -> Self {
+        self.min_fee = min_fee;
+        self
+    }
+
+    pub fn min_fee(&self) -> BoxValue {
+        self.min_fee
+    }
+
+    /// Suggests a fee for a transaction of `tx_size` bytes to be confirmed within the target wait
+    /// time. Starts from the node's recommendation (/transactions/getFee). If the mempool holds
+    /// transactions expected to wait longer than the target, the fee is raised to pay at least the
+    /// fee per byte of the slowest transaction still expected to make it in time, since the
+    /// mempool is ordered by fee per byte. Never lower than the minimum fee
+    pub async fn estimate(&self, tx_size: usize) -> Result<BoxValue, NodeError> {
+        let transactions = self.endpoints.transactions()?;
+        let wait_time_minutes = self.target_wait.as_secs().div_ceil(60) as u32;
+        let node_fee = transactions.get_fee(wait_time_minutes, tx_size).await?;
+
+        let max_time_ms = 2 * self.target_wait.as_millis() as u64;
+        let histogram = transactions
+            .pool_histogram(HISTOGRAM_BINS, max_time_ms)
+            .await?;
+        let target_bins = (HISTOGRAM_BINS / 2) as usize;
+        let congested = histogram.iter().skip(target_bins).any(|bin| bin.n_txns > 0);
+        let congestion_fee = if congested {
+            let in_time = histogram
+                .iter()
+                .take(target_bins)
+                .map(|bin| bin.n_txns as usize)
+                .sum::<usize>();
+            let fee_tree = MINERS_FEE_ADDRESS.script()?;
+            let mut fee_rates = NodeExtension::new(self.endpoints)
+                .get_all_unconfirmed()
+                .await?
+                .iter()
+                .map(|tx| FeeRate::of(tx, &fee_tree))
+                .collect::<Result<Vec<_>, _>>()?;
+            fee_rates.sort_unstable_by(|a, b| b.cmp(a));
+            // with no transaction expected in time, the fee must match the highest rate
+            fee_rates
+                .get(in_time.saturating_sub(1))
+                .map_or(0, |fee_rate| fee_rate.fee_for(tx_size))
+        } else {
+            0
+        };
+        Ok(node_fee
+            .max(congestion_fee)
+            .max(*self.min_fee.as_u64())
+            .try_into()?)
+    }
+
+    /// Suggests a fee for `unsigned_tx` once it's signed, see [`Self::estimate`].
+    /// Assumes every input is proven by a single signature, as with P2PK inputs
+    pub async fn estimate_for(
+        &self,
+        unsigned_tx: &UnsignedTransaction,
+    ) -> Result<BoxValue, NodeError> {
+        let tx_size =
+            unsigned_tx.bytes_to_sign()?.len() + unsigned_tx.inputs.len() * P2PK_PROOF_SIZE;
+        self.estimate(tx_size).await
+    }
+}
+
+/// Fee per byte paid by a mempool transaction, kept as a fraction to compare rates exactly
+#[derive(Debug, Clone, Copy)]
+struct FeeRate {
+    fee: u64,
+    size: usize,
+}
+
+impl FeeRate {
+    fn of(tx: &Transaction, fee_tree: &ErgoTree) -> Result<Self, NodeError> {
+        let fee = tx
+            .outputs
+            .iter()
+            .filter(|output| output.ergo_tree == *fee_tree)
+            .map(|output| *output.value.as_u64())
+            .sum();
+        Ok(Self {
+            fee,
+            size: tx.sigma_serialize_bytes()?.len(),
+        })
+    }
+
+    /// Fee paying this rate for a transaction of `tx_size` bytes
+    fn fee_for(&self, tx_size: usize) -> u64 {
+        (self.fee as u128 * tx_size as u128).div_ceil(self.size as u128) as u64
+    }
+}
+
+impl PartialEq for FeeRate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FeeRate {}
+
+impl PartialOrd for FeeRate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FeeRate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.fee as u128 * other.size as u128).cmp(&(other.fee as u128 * self.size as u128))
+    }
+}
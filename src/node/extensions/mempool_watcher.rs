@@ -0,0 +1,123 @@
+use std::{collections::HashMap, time::Duration};
+
+use ergo_lib::chain::transaction::{Transaction, TxId};
+use futures_util::Stream;
+
+use super::polling::{poll_stream, Poller};
+use super::NodeExtension;
+use crate::node::{
+    endpoints::{scan::TrackingRule, NodeEndpoint},
+    NodeError,
+};
+
+/// Why a transaction left the mempool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalReason {
+    /// The transaction was included in a block
+    Confirmed,
+    /// The transaction was dropped without being included in a block, e.g. because it became
+    /// invalid or the mempool was full
+    Evicted,
+}
+
+#[derive(Debug, Clone)]
+pub enum MempoolEvent {
+    TxAdded(Transaction),
+    TxRemoved(Transaction, RemovalReason),
+}
+
+/// Polls the node's mempool and reports transactions entering and leaving it
+#[derive(Debug)]
+pub struct MempoolWatcher<'a> {
+    endpoints: &'a NodeEndpoint,
+    filter: Option<TrackingRule>,
+    transactions: HashMap<TxId, Transaction>,
+}
+
+impl<'a> MempoolWatcher<'a> {
+    pub fn new(endpoints: &'a NodeEndpoint) -> Self {
+        Self {
+            endpoints,
+            filter: None,
+            transactions: HashMap::new(),
+        }
+    }
+
+    /// Only report transactions with at least one output matching `filter`.
+    /// Use the helpers in [`crate::node::endpoints::scan::rule`] to filter by address, ErgoTree
+    /// or token id
+    pub fn with_filter(mut self, filter: TrackingRule) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Fetches the mempool once and returns the events that happened since the last poll.
+    /// The first poll reports every matching transaction currently in the mempool as added
+    pub async fn poll(&mut self) -> Result<Vec<MempoolEvent>, NodeError> {
+        let mut current = HashMap::new();
+        for tx in NodeExtension::new(self.endpoints)
+            .get_all_unconfirmed()
+            .await?
+        {
+            let matches = match &self.filter {
+                Some(filter) => tx.outputs.iter().any(|output| filter.matches(output)),
+                None => true,
+            };
+            if matches {
+                current.insert(tx.id(), tx);
+            }
+        }
+
+        let mut events = vec![];
+        for (tx_id, tx) in &self.transactions {
+            if !current.contains_key(tx_id) {
+                let reason = self.removal_reason(tx).await?;
+                events.push(MempoolEvent::TxRemoved(tx.clone(), reason));
+            }
+        }
+        for (tx_id, tx) in &current {
+            if !self.transactions.contains_key(tx_id) {
+                events.push(MempoolEvent::TxAdded(tx.clone()));
+            }
+        }
+        self.transactions = current;
+        Ok(events)
+    }
+
+    /// A confirmed transaction's outputs are in the UTXO set unless they were already spent, in
+    /// which case the indexer (if enabled) still knows about the transaction
+    async fn removal_reason(&self, tx: &Transaction) -> Result<RemovalReason, NodeError> {
+        let utxo = self.endpoints.utxo()?;
+        for output in tx.outputs.iter() {
+            if utxo.find_by_id(&output.box_id()).await?.is_some() {
+                return Ok(RemovalReason::Confirmed);
+            }
+        }
+        match self
+            .endpoints
+            .blockchain()?
+            .find_transaction_by_id(&tx.id())
+            .await?
+        {
+            Some(_) => Ok(RemovalReason::Confirmed),
+            None => Ok(RemovalReason::Evicted),
+        }
+    }
+
+    /// Turns the watcher into a stream of events, polling the node every `poll_interval`.
+    /// Errors are yielded as they occur and polling continues afterwards
+    pub fn into_stream(
+        self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<MempoolEvent, NodeError>> + 'a {
+        poll_stream(self, poll_interval)
+    }
+}
+
+impl Poller for MempoolWatcher<'_> {
+    type Event = MempoolEvent;
+
+    async fn poll(&mut self) -> Result<Vec<MempoolEvent>, NodeError> {
+        MempoolWatcher::poll(self).await
+    }
+}
@@ -0,0 +1,58 @@
+use std::{collections::VecDeque, time::Duration};
+
+use futures_util::{stream, Stream};
+
+use crate::node::NodeError;
+
+/// Something polling the node for events, turned into a stream by [`poll_stream`]
+pub(super) trait Poller {
+    type Event;
+
+    /// Queries the node once and returns the events since the last poll
+    async fn poll(&mut self) -> Result<Vec<Self::Event>, NodeError>;
+
+    /// Ends the stream once the pending events are yielded
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    /// Whether to wait for the poll interval after a poll returning `events`, rather than polling
+    /// again right away
+    fn should_wait(&self, _events: &[Self::Event]) -> bool {
+        true
+    }
+}
+
+/// Polls `poller` every `poll_interval`, yielding its events one by one.
+/// Errors are yielded as they occur and polling continues afterwards
+pub(super) fn poll_stream<'a, P: Poller + 'a>(
+    poller: P,
+    poll_interval: Duration,
+) -> impl Stream<Item = Result<P::Event, NodeError>> + 'a {
+    stream::unfold(
+        (poller, VecDeque::new(), false),
+        move |(mut poller, mut pending, mut wait)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (poller, pending, wait)));
+                }
+                if poller.is_finished() {
+                    return None;
+                }
+                if wait {
+                    tokio::time::sleep(poll_interval).await;
+                }
+                match poller.poll().await {
+                    Ok(events) => {
+                        wait = poller.should_wait(&events);
+                        pending.extend(events);
+                    }
+                    Err(e) => {
+                        wait = true;
+                        return Some((Err(e), (poller, pending, wait)));
+                    }
+                }
+            }
+        },
+    )
+}
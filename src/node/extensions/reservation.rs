@@ -0,0 +1,95 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use ergo_lib::ergotree_ir::chain::ergo_box::{BoxId, ErgoBox};
+
+/// How long selected boxes stay reserved if the transaction spending them is never submitted
+pub const DEFAULT_RESERVATION_TTL: Duration = Duration::from_secs(120);
+
+/// How long boxes spent by a submitted transaction are kept out of selection. The wallet keeps
+/// reporting them as unspent until the transaction is included in a block
+pub const DEFAULT_SPENT_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug)]
+struct Reservations {
+    ttl: Duration,
+    spent_ttl: Duration,
+    /// Expiry of every box that is reserved or spent
+    leases: HashMap<BoxId, Instant>,
+}
+
+/// Boxes selected by [`super::NodeExtension`] that are waiting for their transaction to be
+/// submitted, or were spent by a submitted transaction. Shared between clones, so concurrent
+/// transaction builders on one [`crate::node::NodeClient`] never select the same inputs
+#[derive(Debug, Clone)]
+pub struct BoxReservations(Arc<Mutex<Reservations>>);
+
+impl Default for BoxReservations {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESERVATION_TTL, DEFAULT_SPENT_TTL)
+    }
+}
+
+impl BoxReservations {
+    /// Selected boxes are released after `ttl` unless their transaction is submitted,
+    /// and boxes spent by a submitted transaction are released after `spent_ttl`
+    pub fn new(ttl: Duration, spent_ttl: Duration) -> Self {
+        Self(Arc::new(Mutex::new(Reservations {
+            ttl,
+            spent_ttl,
+            leases: HashMap::new(),
+        })))
+    }
+
+    /// Locks the reservations, dropping expired leases. Holding the guard across selection and
+    /// [`ReservationGuard::reserve`] makes both a single step for concurrent builders
+    pub(super) fn lock(&self) -> ReservationGuard<'_> {
+        let mut reservations = self.0.lock().unwrap();
+        let now = Instant::now();
+        reservations.leases.retain(|_, expiry| *expiry > now);
+        ReservationGuard(reservations)
+    }
+
+    pub fn is_reserved(&self, box_id: &BoxId) -> bool {
+        self.lock().0.leases.contains_key(box_id)
+    }
+
+    /// Makes `box_ids` available for selection again
+    pub fn release(&self, box_ids: &[BoxId]) {
+        let mut reservations = self.0.lock().unwrap();
+        for box_id in box_ids {
+            reservations.leases.remove(box_id);
+        }
+    }
+
+    /// Keeps `box_ids` out of selection for the spent TTL, after their transaction was submitted
+    pub fn mark_spent(&self, box_ids: &[BoxId]) {
+        let mut reservations = self.0.lock().unwrap();
+        let expiry = Instant::now() + reservations.spent_ttl;
+        for box_id in box_ids {
+            reservations.leases.insert(*box_id, expiry);
+        }
+    }
+}
+
+pub(super) struct ReservationGuard<'a>(MutexGuard<'a, Reservations>);
+
+impl ReservationGuard<'_> {
+    /// Removes reserved and spent boxes from `boxes`
+    pub fn available(&self, boxes: Vec<ErgoBox>) -> Vec<ErgoBox> {
+        boxes
+            .into_iter()
+            .filter(|b| !self.0.leases.contains_key(&b.box_id()))
+            .collect()
+    }
+
+    pub fn reserve(&mut self, box_ids: impl IntoIterator<Item = BoxId>) {
+        let expiry = Instant::now() + self.0.ttl;
+        for box_id in box_ids {
+            self.0.leases.insert(box_id, expiry);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::{collections::HashMap, time::Duration};
+
+use ergo_lib::ergotree_ir::chain::ergo_box::BoxId;
+use futures_util::Stream;
+
+use super::polling::{poll_stream, Poller};
+use crate::node::{
+    endpoints::{
+        scan::{ScanBox, ScanQuery},
+        NodeEndpoint,
+    },
+    NodeError,
+};
+
+#[derive(Debug, Clone)]
+pub enum ScanEvent {
+    /// A box was seen in the scan for the first time
+    BoxCreated(ScanBox),
+    /// A box was included in a block
+    BoxConfirmed(ScanBox),
+    /// A box was spent, either by a mempool transaction or in a block
+    BoxSpent(ScanBox),
+    /// A box left the scan without being spent, e.g. an unconfirmed box whose transaction was
+    /// dropped from the mempool
+    BoxRemoved(ScanBox),
+}
+
+/// Polls a scan's unspent and spent boxes and reports the changes between polls as [`ScanEvent`]s
+#[derive(Debug)]
+pub struct ScanFeed<'a> {
+    endpoints: &'a NodeEndpoint,
+    scan_id: u32,
+    include_unconfirmed: bool,
+    boxes: HashMap<BoxId, ScanBox>,
+    /// Highest inclusion height seen so far. Boxes that were unconfirmed on the last poll can't
+    /// have been included below it, which bounds the search for spent boxes
+    max_inclusion_height: u32,
+}
+
+impl<'a> ScanFeed<'a> {
+    pub fn new(endpoints: &'a NodeEndpoint, scan_id: u32, include_unconfirmed: bool) -> Self {
+        Self {
+            endpoints,
+            scan_id,
+            include_unconfirmed,
+            boxes: HashMap::new(),
+            max_inclusion_height: 0,
+        }
+    }
+
+    /// Fetches the scan's boxes once and returns the events that happened since the last poll.
+    /// The first poll reports every box currently in the scan as created
+    pub async fn poll(&mut self) -> Result<Vec<ScanEvent>, NodeError> {
+        let mut events = vec![];
+        let mut current = HashMap::new();
+        for scan_box in self.fetch_all(ScanQuery::default(), false).await? {
+            let box_id = scan_box.ergo_box.box_id();
+            match self.boxes.get(&box_id) {
+                None => {
+                    events.push(ScanEvent::BoxCreated(scan_box.clone()));
+                    if scan_box.confirmations > 0 {
+                        events.push(ScanEvent::BoxConfirmed(scan_box.clone()));
+                    }
+                    if scan_box.spending_transaction.is_some() {
+                        events.push(ScanEvent::BoxSpent(scan_box.clone()));
+                    }
+                }
+                Some(previous) => {
+                    if previous.confirmations == 0 && scan_box.confirmations > 0 {
+                        events.push(ScanEvent::BoxConfirmed(scan_box.clone()));
+                    }
+                    if previous.spending_transaction.is_none()
+                        && scan_box.spending_transaction.is_some()
+                    {
+                        events.push(ScanEvent::BoxSpent(scan_box.clone()));
+                    }
+                }
+            }
+            current.insert(box_id, scan_box);
+        }
+
+        let gone = self
+            .boxes
+            .iter()
+            .filter(|(box_id, _)| !current.contains_key(box_id))
+            .collect::<Vec<_>>();
+        if !gone.is_empty() {
+            let min_inclusion_height = gone
+                .iter()
+                .map(|(_, b)| b.inclusion_height.unwrap_or(self.max_inclusion_height))
+                .min()
+                .unwrap_or(0);
+            let query = ScanQuery {
+                min_inclusion_height: min_inclusion_height as i32,
+                ..Default::default()
+            };
+            let mut spent = self
+                .fetch_all(query, true)
+                .await?
+                .into_iter()
+                .map(|b| (b.ergo_box.box_id(), b))
+                .collect::<HashMap<_, _>>();
+            for (box_id, previous) in gone {
+                match spent.remove(box_id) {
+                    Some(spent_box) => {
+                        if previous.spending_transaction.is_none() {
+                            events.push(ScanEvent::BoxSpent(spent_box));
+                        }
+                    }
+                    None => events.push(ScanEvent::BoxRemoved(previous.clone())),
+                }
+            }
+        }
+
+        // only update the known boxes once every request succeeded, so a failed poll can be retried
+        self.boxes = current;
+        self.max_inclusion_height = self
+            .boxes
+            .values()
+            .filter_map(|b| b.inclusion_height)
+            .chain([self.max_inclusion_height])
+            .max()
+            .unwrap_or_default();
+        Ok(events)
+    }
+
+    async fn fetch_all(
+        &self,
+        mut scan_query: ScanQuery,
+        spent: bool,
+    ) -> Result<Vec<ScanBox>, NodeError> {
+        if self.include_unconfirmed {
+            scan_query.min_confirmations = -1;
+        }
+        scan_query.limit = 2500;
+        let scan_endpoint = self.endpoints.scan()?;
+        let mut boxes = vec![];
+        loop {
+            let new_boxes = if spent {
+                scan_endpoint.spent_boxes(self.scan_id, &scan_query).await?
+            } else {
+                scan_endpoint
+                    .unspent_boxes(self.scan_id, &scan_query)
+                    .await?
+            };
+            if new_boxes.is_empty() {
+                break;
+            }
+            scan_query.offset += new_boxes.len() as u32;
+            boxes.extend(new_boxes);
+        }
+        Ok(boxes)
+    }
+
+    /// Turns the feed into a stream of events, polling the node every `poll_interval`.
+    /// Errors are yielded as they occur and polling continues afterwards
+    pub fn into_stream(
+        self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<ScanEvent, NodeError>> + 'a {
+        poll_stream(self, poll_interval)
+    }
+}
+
+impl Poller for ScanFeed<'_> {
+    type Event = ScanEvent;
+
+    async fn poll(&mut self) -> Result<Vec<ScanEvent>, NodeError> {
+        ScanFeed::poll(self).await
+    }
+}
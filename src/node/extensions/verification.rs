@@ -0,0 +1,68 @@
+use ergo_lib::{
+    chain::{
+        ergo_state_context::ErgoStateContext,
+        transaction::{ergo_transaction::TxValidationError, verify_tx_input_proof, Transaction},
+    },
+    ergotree_interpreter::sigma_protocol::verifier::VerificationResult,
+    ergotree_ir::chain::ergo_box::BoxId,
+    wallet::{signing::make_context, tx_context::TransactionContext},
+};
+
+/// Outcome of verifying one input's proof
+#[derive(Debug)]
+pub struct InputVerification {
+    pub box_id: BoxId,
+    /// Reduction result and cost, or the error that stopped evaluation of the input's script.
+    /// `result` is `false` when the script was evaluated but the proof doesn't satisfy it
+    pub result: Result<VerificationResult, TxValidationError>,
+}
+
+impl InputVerification {
+    pub fn is_valid(&self) -> bool {
+        matches!(self.result, Ok(VerificationResult { result: true, .. }))
+    }
+}
+
+/// Report produced by [`super::NodeExtension::verify_transaction`]
+#[derive(Debug)]
+pub struct TransactionVerification {
+    /// Result of the full stateful validation done by the node before accepting a transaction:
+    /// ERG and token preservation, output validity and input proofs. Stops at the first failure
+    pub validation: Result<(), TxValidationError>,
+    /// Result of verifying every input's proof on its own, in input order
+    pub inputs: Vec<InputVerification>,
+}
+
+impl TransactionVerification {
+    pub fn new(
+        tx_context: &TransactionContext<Transaction>,
+        state_context: &ErgoStateContext,
+    ) -> Result<Self, TxValidationError> {
+        let bytes_to_sign = tx_context.spending_tx.bytes_to_sign()?;
+        let mut context = make_context(state_context, tx_context, 0)?;
+        let inputs = tx_context
+            .spending_tx
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(input_idx, input)| InputVerification {
+                box_id: input.box_id,
+                result: verify_tx_input_proof(
+                    tx_context,
+                    &mut context,
+                    state_context,
+                    input_idx,
+                    &bytes_to_sign,
+                ),
+            })
+            .collect();
+        Ok(Self {
+            validation: tx_context.validate(state_context),
+            inputs,
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validation.is_ok()
+    }
+}